@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Command, Range, TextEdit, Url, WorkspaceEdit,
+};
+use serde_json::Value;
+
+use crate::cache::{list_dir_entries, DirCache};
+use crate::completion::resolve_literal_paths;
+use crate::config::Config;
+use crate::diagnostics::{missing_path_diagnostic, MissingPath};
+
+pub const CREATE_PATH_COMMAND: &str = "pathy.createPath";
+
+const MAX_SUGGESTIONS: usize = 3;
+
+pub struct ActionContext<'a> {
+    pub uri: &'a Url,
+    pub file_dir: Option<&'a Path>,
+    pub root_dir: Option<&'a Path>,
+    pub config: &'a Config,
+}
+
+pub fn code_actions(
+    ctx: &ActionContext,
+    missing: &[MissingPath],
+    range: Range,
+    cache: &mut DirCache,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    for item in missing.iter().filter(|m| ranges_overlap(m.range, range)) {
+        let diagnostics = missing_path_diagnostic(item, ctx.config).map(|d| vec![d]);
+        // Suggestions are built for the joined path and keep only those that
+        // leave the base the literal is joined onto untouched.
+        let base = &item.path[..item.path.len() - item.content.len()];
+        let suggestions =
+            suggest_replacements(&item.path, ctx.file_dir, ctx.root_dir, cache, ctx.config);
+        let escaped = suggestions.into_iter().filter_map(|suggestion| {
+            let suggestion = suggestion.strip_prefix(base)?.to_string();
+            Some((item.kind.escape(&suggestion)?, suggestion))
+        });
+        for (idx, (new_text, suggestion)) in escaped.enumerate() {
+            let edit = TextEdit {
+                range: item.range,
+                new_text,
+            };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Did you mean \"{suggestion}\"?"),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: diagnostics.clone(),
+                edit: Some(WorkspaceEdit {
+                    changes: Some(HashMap::from([(ctx.uri.clone(), vec![edit])])),
+                    ..WorkspaceEdit::default()
+                }),
+                is_preferred: Some(idx == 0),
+                ..CodeAction::default()
+            }));
+        }
+
+        let Some(target) =
+            resolve_literal_paths(&item.path, ctx.file_dir, ctx.root_dir, ctx.config)
+                .into_iter()
+                .next()
+        else {
+            continue;
+        };
+        let ends_with_sep = item.content.ends_with(['/', '\\']);
+        let mut kinds = Vec::new();
+        if !ends_with_sep {
+            kinds.push(false);
+        }
+        let has_extension = target
+            .file_name()
+            .map(|name| name.to_string_lossy().contains('.'))
+            .unwrap_or(false);
+        if ends_with_sep || !has_extension {
+            kinds.push(true);
+        }
+        for is_dir in kinds {
+            let noun = if is_dir { "directory" } else { "file" };
+            let title = format!("Create {noun} \"{}\"", item.content);
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: title.clone(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: diagnostics.clone(),
+                command: Some(Command {
+                    title,
+                    command: CREATE_PATH_COMMAND.into(),
+                    arguments: Some(vec![
+                        Value::String(target.to_string_lossy().to_string()),
+                        Value::Bool(is_dir),
+                    ]),
+                }),
+                ..CodeAction::default()
+            }));
+        }
+    }
+    actions
+}
+
+/// Creates the file or directory requested by a `pathy.createPath` action and
+/// returns the directory whose listing changed.
+pub fn execute_create_path(arguments: &[Value]) -> Result<PathBuf, String> {
+    let Some(path) = arguments.first().and_then(Value::as_str) else {
+        return Err("missing path argument".into());
+    };
+    let is_dir = arguments.get(1).and_then(Value::as_bool).unwrap_or(false);
+    let path = PathBuf::from(path);
+    if is_dir {
+        std::fs::create_dir_all(&path).map_err(|err| err.to_string())?;
+    } else {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+        }
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(|err| err.to_string())?;
+    }
+    Ok(path.parent().map(Path::to_path_buf).unwrap_or(path))
+}
+
+/// Rebuilds a broken literal one component at a time, replacing each missing
+/// component with the closest names found in its parent directory.
+pub fn suggest_replacements(
+    content: &str,
+    file_dir: Option<&Path>,
+    root_dir: Option<&Path>,
+    cache: &mut DirCache,
+    config: &Config,
+) -> Vec<String> {
+    let mut built = vec![String::new()];
+    let mut fanned_out = false;
+    let mut cursor = 0usize;
+    for (start, end) in component_ranges(content) {
+        let leading = &content[cursor..start];
+        let name = &content[start..end];
+        cursor = end;
+
+        let mut next = Vec::new();
+        let mut fan_out_here = false;
+        for prefix in &built {
+            let base = format!("{prefix}{leading}");
+            let exact = format!("{base}{name}");
+            if is_anchor(name) || literal_exists(&exact, file_dir, root_dir, config) {
+                next.push(exact);
+                continue;
+            }
+            let Some(dir) = resolve_literal_paths(&base, file_dir, root_dir, config)
+                .into_iter()
+                .find(|path| path.is_dir())
+            else {
+                continue;
+            };
+            let entries = list_dir_entries(&dir, cache, config).unwrap_or_default();
+            let names = entries.into_iter().map(|(name, _, _)| name);
+            let limit = if fanned_out { 1 } else { MAX_SUGGESTIONS };
+            for candidate in rank_similar_names(name, names, limit) {
+                next.push(format!("{base}{candidate}"));
+            }
+            fan_out_here = true;
+        }
+        fanned_out |= fan_out_here;
+        built = next;
+        if built.is_empty() {
+            return Vec::new();
+        }
+    }
+
+    let trailing = &content[cursor..];
+    let mut suggestions = Vec::new();
+    for prefix in built {
+        let suggestion = format!("{prefix}{trailing}");
+        if suggestion != content && !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    }
+    suggestions.truncate(MAX_SUGGESTIONS);
+    suggestions
+}
+
+/// Ranks `names` by case-insensitive edit distance to `target`, breaking ties
+/// with the case-sensitive distance.
+pub fn rank_similar_names(
+    target: &str,
+    names: impl IntoIterator<Item = String>,
+    limit: usize,
+) -> Vec<String> {
+    let target_lower = target.to_lowercase();
+    let max_distance = (target.chars().count() / 2).max(2);
+    let mut scored: Vec<(usize, usize, String)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let distance = edit_distance(&target_lower, &name.to_lowercase());
+            if distance > max_distance {
+                return None;
+            }
+            Some((distance, edit_distance(target, &name), name))
+        })
+        .collect();
+    scored.sort();
+    scored.into_iter().take(limit).map(|(_, _, n)| n).collect()
+}
+
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b_chars: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b_chars.len()).collect();
+    for (i, a_ch) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_ch) in b_chars.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_ch != *b_ch);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b_chars.len()]
+}
+
+fn component_ranges(content: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (idx, ch) in content.char_indices() {
+        if ch == '/' || ch == '\\' {
+            if let Some(s) = start.take() {
+                ranges.push((s, idx));
+            }
+        } else if start.is_none() {
+            start = Some(idx);
+        }
+    }
+    if let Some(s) = start {
+        ranges.push((s, content.len()));
+    }
+    ranges
+}
+
+fn is_anchor(name: &str) -> bool {
+    matches!(name, "." | ".." | "~")
+        || (name.len() == 2 && name.ends_with(':') && name.as_bytes()[0].is_ascii_alphabetic())
+}
+
+fn literal_exists(
+    content: &str,
+    file_dir: Option<&Path>,
+    root_dir: Option<&Path>,
+    config: &Config,
+) -> bool {
+    resolve_literal_paths(content, file_dir, root_dir, config)
+        .iter()
+        .any(|path| path.exists())
+}
+
+fn ranges_overlap(a: Range, b: Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn edit_distance_counts_operations() {
+        assert_eq!(edit_distance("datsets", "datasets"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn ranks_case_insensitive_matches_first() {
+        let names = vec!["Data".to_string(), "date".to_string(), "models".to_string()];
+        let ranked = rank_similar_names("data", names, 3);
+        assert_eq!(ranked, vec!["Data".to_string(), "date".to_string()]);
+    }
+
+    #[test]
+    fn suggests_fix_for_misspelled_directory() {
+        let root = TempDir::new("actions");
+        std::fs::create_dir_all(root.join("datasets")).unwrap();
+        std::fs::write(root.join("datasets").join("train.csv"), "").unwrap();
+
+        let mut cache = DirCache::new(Duration::from_millis(0), 8);
+        let config = Config::default();
+        let suggestions = suggest_replacements(
+            "./datsets/train.csv",
+            Some(&root),
+            None,
+            &mut cache,
+            &config,
+        );
+
+        assert_eq!(suggestions, vec!["./datasets/train.csv".to_string()]);
+    }
+}
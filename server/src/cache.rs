@@ -2,6 +2,8 @@ use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+use crate::config::{Config, StatStrategy};
+
 #[derive(Clone, Debug)]
 pub struct DirEntryInfo {
     pub name: String,
@@ -58,6 +60,10 @@ impl DirCache {
         }
     }
 
+    pub fn invalidate(&mut self, dir: &Path) {
+        self.entries.retain(|entry| entry.dir != dir);
+    }
+
     pub fn update_limits(&mut self, ttl: Duration, max_entries: usize) {
         self.ttl = ttl;
         self.max_entries = max_entries;
@@ -66,3 +72,46 @@ impl DirCache {
         }
     }
 }
+
+pub fn list_dir_entries(
+    dir: &Path,
+    cache: &mut DirCache,
+    config: &Config,
+) -> Option<Vec<(String, bool, PathBuf)>> {
+    if let Some(cached) = cache.get(dir) {
+        let entries = cached
+            .into_iter()
+            .map(|e| {
+                let name = e.name;
+                let path = dir.join(&name);
+                (name, e.is_dir, path)
+            })
+            .collect();
+        return Some(entries);
+    }
+
+    let mut items: Vec<DirEntryInfo> = Vec::new();
+    let mut entries: Vec<(String, bool, PathBuf)> = Vec::new();
+    let read_dir = std::fs::read_dir(dir).ok()?;
+    for entry in read_dir {
+        let Ok(entry) = entry else { continue };
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        // `file_type` comes from the directory listing itself on most
+        // platforms; only `eager` pays for a full stat (following symlinks).
+        let is_dir = match config.stat_strategy {
+            StatStrategy::None => false,
+            StatStrategy::Lazy => entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
+            StatStrategy::Eager => std::fs::metadata(entry.path())
+                .map(|m| m.is_dir())
+                .unwrap_or(false),
+        };
+        items.push(DirEntryInfo {
+            name: file_name.clone(),
+            is_dir,
+        });
+        entries.push((file_name, is_dir, dir.join(entry.file_name())));
+    }
+
+    cache.insert(dir, items);
+    Some(entries)
+}
@@ -1,8 +1,12 @@
 use std::path::{Path, PathBuf};
 
-use lsp_types::Url;
+use lsp_types::{Position, Range, Url};
+use unicode_normalization::{is_nfc, UnicodeNormalization};
 
-use crate::config::{BaseDirStrategy, Config, WorkspaceRootStrategy};
+use crate::config::{BaseDirStrategy, Config, MatchCase, MatchingStrategy, WorkspaceRootStrategy};
+use crate::context::PathExpectation;
+use crate::ignore::GitIgnore;
+use crate::tokenizer::{group_start, token_at, LiteralKind, StringToken};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrefixKind {
@@ -23,186 +27,227 @@ pub struct PathQuery {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StringInfo {
+    /// Path text before the cursor, with escapes decoded: earlier strings of
+    /// an implicitly concatenated group followed by this string's value up to
+    /// the cursor.
     pub content_before_cursor: String,
-    pub is_raw: bool,
-    pub is_fstring: bool,
+    /// How the string containing the cursor is written.
+    pub kind: LiteralKind,
+    /// Document offset of the opening quote of the group's first string.
     pub string_start_byte: usize,
-    pub string_start_utf16: u32,
+    /// UTF-16 column on the cursor line where the segment being typed begins.
+    pub segment_start_utf16: u32,
 }
 
-pub fn find_string_info(line: &str, cursor_byte: usize) -> Option<StringInfo> {
-    let mut in_string: Option<StringState> = None;
-    let mut i = 0usize;
-    while i < line.len() {
-        if i == cursor_byte {
-            if let Some(state) = &in_string {
-                let content_start = state.start_byte + state.delim_len;
-                if cursor_byte >= content_start {
-                    let content = &line[content_start..cursor_byte];
-                    if state.is_fstring && is_in_interpolation(content, state.is_raw) {
-                        return None;
-                    }
-                    let string_start_utf16 = utf16_len(&line[..content_start]);
-                    return Some(StringInfo {
-                        content_before_cursor: content.to_string(),
-                        is_raw: state.is_raw,
-                        is_fstring: state.is_fstring,
-                        string_start_byte: state.start_byte,
-                        string_start_utf16,
-                    });
-                }
-            }
-        }
+/// Describes the string literal containing `cursor` (a document offset), or
+/// `None` when the cursor is outside any string or inside an f-string
+/// interpolation.
+pub fn find_string_info(text: &str, tokens: &[StringToken], cursor: usize) -> Option<StringInfo> {
+    let idx = token_at(tokens, cursor)?;
+    let token = &tokens[idx];
+    if !token.valid_prefix {
+        return None;
+    }
+    let kind = token.kind();
+    let raw_before = &text[token.content_start..cursor];
+    if token.is_fstring && is_in_interpolation(raw_before, token.is_raw) {
+        return None;
+    }
+    let line_part_start = raw_before
+        .rfind('\n')
+        .map_or(token.content_start, |nl| token.content_start + nl + 1);
 
-        let ch = line[i..].chars().next()?;
-        let ch_len = ch.len_utf8();
-        if in_string.is_none() && ch == '#' {
-            break;
-        }
+    let first = group_start(tokens, idx);
+    let mut prefix: String = tokens[first..idx]
+        .iter()
+        .map(|piece| piece_content(text, piece))
+        .collect();
+    // Lines of a triple-quoted string are independent; a single-quoted string
+    // only spans lines through backslash continuations.
+    if !token.triple {
+        prefix.push_str(&kind.unescape(&text[token.content_start..line_part_start]));
+    }
+    let line_raw = &text[line_part_start..cursor];
+    let line_text = kind.unescape(line_raw);
+    if !prefix.is_empty() && !prefix.ends_with(['/', '\\']) && !line_text.contains(['/', '\\']) {
+        // The segment being typed starts in an earlier piece, so no
+        // replacement range on this line can cover it.
+        return None;
+    }
+    let line_start = text[..cursor].rfind('\n').map_or(0, |nl| nl + 1);
+    let segment_start = line_part_start + kind.segment_start(line_raw);
+    Some(StringInfo {
+        content_before_cursor: format!("{prefix}{line_text}"),
+        kind,
+        string_start_byte: tokens[first].start,
+        segment_start_utf16: utf16_len(&text[line_start..segment_start]),
+    })
+}
 
-        if let Some(state) = &mut in_string {
-            if !state.is_raw && ch == '\\' {
-                i += ch_len;
-                if i < line.len() {
-                    let next = line[i..].chars().next()?;
-                    i += next.len_utf8();
-                    continue;
-                }
-            }
-            if state.delim_len == 1 && ch == state.quote {
-                in_string = None;
-            } else if state.delim_len == 3 && line[i..].starts_with(state.delim.as_str()) {
-                i += state.delim_len;
-                in_string = None;
-                continue;
-            }
-            i += ch_len;
-            continue;
-        }
+/// A closed string literal, or an implicitly concatenated group of them,
+/// with byte offsets into the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringLiteral {
+    /// The literal's value, with escapes decoded.
+    pub content: String,
+    /// How the first string of the group is written; replacement text for
+    /// `content_range` must be escaped for it.
+    pub kind: LiteralKind,
+    /// Whether any string of the group is an f-string.
+    pub is_fstring: bool,
+    pub string_start_byte: usize,
+    pub content_start_byte: usize,
+    pub content_end_byte: usize,
+    pub content_range: Range,
+}
 
-        if ch == '\'' || ch == '"' {
-            let (is_raw, is_fstring) = detect_prefix(line, i);
-            let delim_len = if line[i..].starts_with("\"\"\"") || line[i..].starts_with("'''") {
-                3
-            } else {
-                1
-            };
-            let delim = if delim_len == 3 {
-                if ch == '\'' {
-                    "'''"
-                } else {
-                    "\"\"\""
-                }
-            } else {
-                ""
-            };
-            in_string = Some(StringState {
-                quote: ch,
-                delim_len,
-                delim: delim.to_string(),
-                start_byte: i,
-                is_raw,
-                is_fstring,
-            });
-            i += ch_len;
+/// Returns every closed literal in the document, joining implicitly
+/// concatenated strings into one literal.
+pub fn find_string_literals(text: &str, tokens: &[StringToken]) -> Vec<StringLiteral> {
+    let mut literals = Vec::new();
+    let mut positions = PositionCursor::new(text);
+    let mut idx = 0usize;
+    while idx < tokens.len() {
+        let mut end = idx + 1;
+        while end < tokens.len() && tokens[end].continues_previous {
+            end += 1;
+        }
+        let group = &tokens[idx..end];
+        idx = end;
+        if !group
+            .iter()
+            .all(|token| token.terminated && token.valid_prefix)
+        {
             continue;
         }
+        let first = &group[0];
+        let last = &group[group.len() - 1];
+        let start = positions.advance_to(first.content_start);
+        let end = positions.advance_to(last.content_end);
+        literals.push(StringLiteral {
+            content: group
+                .iter()
+                .map(|piece| piece_content(text, piece))
+                .collect(),
+            kind: first.kind(),
+            is_fstring: group.iter().any(|piece| piece.is_fstring),
+            string_start_byte: first.start,
+            content_start_byte: first.content_start,
+            content_end_byte: last.content_end,
+            content_range: Range { start, end },
+        });
+    }
+    literals
+}
 
-        i += ch_len;
-    }
-
-    if cursor_byte == line.len() {
-        if let Some(state) = in_string {
-            let content_start = state.start_byte + state.delim_len;
-            if cursor_byte >= content_start {
-                let content = &line[content_start..cursor_byte];
-                if state.is_fstring && is_in_interpolation(content, state.is_raw) {
-                    return None;
-                }
-                let string_start_utf16 = utf16_len(&line[..content_start]);
-                return Some(StringInfo {
-                    content_before_cursor: content.to_string(),
-                    is_raw: state.is_raw,
-                    is_fstring: state.is_fstring,
-                    string_start_byte: state.start_byte,
-                    string_start_utf16,
-                });
-            }
-        }
-    }
+/// Returns the closed literal whose contents contain the document offset.
+pub fn find_string_literal_at(
+    text: &str,
+    tokens: &[StringToken],
+    offset: usize,
+) -> Option<StringLiteral> {
+    let idx = token_at(tokens, offset)?;
+    let first = group_start(tokens, idx);
+    let mut last = idx;
+    while last + 1 < tokens.len() && tokens[last + 1].continues_previous {
+        last += 1;
+    }
+    find_string_literals(text, &tokens[first..=last]).pop()
+}
 
-    None
+fn piece_content(text: &str, token: &StringToken) -> String {
+    token
+        .kind()
+        .unescape(&text[token.content_start..token.content_end])
 }
 
-#[derive(Debug, Clone)]
-struct StringState {
-    quote: char,
-    delim_len: usize,
-    delim: String,
-    start_byte: usize,
-    is_raw: bool,
-    is_fstring: bool,
+/// Converts increasing document offsets to LSP positions in a single pass.
+struct PositionCursor<'a> {
+    text: &'a str,
+    offset: usize,
+    position: Position,
 }
 
-fn detect_prefix(line: &str, quote_idx: usize) -> (bool, bool) {
-    let mut prefix = String::new();
-    let mut i = quote_idx;
-    while i > 0 {
-        let prev = line[..i].chars().last().unwrap_or(' ');
-        if !prev.is_ascii_alphabetic() {
-            break;
-        }
-        prefix.push(prev.to_ascii_lowercase());
-        i -= prev.len_utf8();
-        if prefix.len() >= 2 {
-            break;
+impl<'a> PositionCursor<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            position: Position::new(0, 0),
         }
     }
 
-    if i > 0 {
-        let before = line[..i].chars().last().unwrap_or(' ');
-        if before.is_ascii_alphanumeric() || before == '_' {
-            return (false, false);
+    fn advance_to(&mut self, offset: usize) -> Position {
+        for ch in self.text[self.offset..offset].chars() {
+            if ch == '\n' {
+                self.position.line += 1;
+                self.position.character = 0;
+            } else {
+                self.position.character += ch.len_utf16() as u32;
+            }
         }
+        self.offset = offset;
+        self.position
     }
+}
 
-    let is_raw = prefix.contains('r');
-    let is_fstring = prefix.contains('f');
-    (is_raw, is_fstring)
+/// One open replacement field of an f-string: either its expression, with
+/// the depth of brackets opened inside it, or its format spec.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Expression { brackets: usize },
+    FormatSpec,
 }
 
+/// Whether the end of `content` (the raw text of an f-string up to the
+/// cursor) lies inside a replacement field. Format specs may nest fields, as
+/// in `{x:{width}}`, and expressions may contain strings and brackets.
 fn is_in_interpolation(content: &str, is_raw: bool) -> bool {
-    let mut depth = 0i32;
+    let bytes = content.as_bytes();
+    let mut fields: Vec<Field> = Vec::new();
     let mut i = 0usize;
-    while i < content.len() {
-        let ch = content[i..].chars().next().unwrap();
-        let ch_len = ch.len_utf8();
-        if !is_raw && ch == '\\' {
-            i += ch_len;
-            if i < content.len() {
-                let next = content[i..].chars().next().unwrap();
-                i += next.len_utf8();
-            }
-            continue;
-        }
-        if ch == '{' {
-            if content[i + ch_len..].starts_with('{') {
-                i += ch_len * 2;
-                continue;
-            }
-            depth += 1;
-        } else if ch == '}' {
-            if content[i + ch_len..].starts_with('}') {
-                i += ch_len * 2;
-                continue;
-            }
-            if depth > 0 {
-                depth -= 1;
-            }
+    while i < bytes.len() {
+        let byte = bytes[i];
+        match fields.last_mut() {
+            Some(Field::Expression { brackets }) => match byte {
+                b'\'' | b'"' => {
+                    i += 1;
+                    while i < bytes.len() && bytes[i] != byte {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                b'(' | b'[' | b'{' => *brackets += 1,
+                b')' | b']' => *brackets = brackets.saturating_sub(1),
+                b'}' if *brackets > 0 => *brackets -= 1,
+                b'}' => {
+                    fields.pop();
+                }
+                b':' if *brackets == 0 => *fields.last_mut().unwrap() = Field::FormatSpec,
+                _ => {}
+            },
+            // Literal text, at the top level or inside a format spec.
+            top => match byte {
+                b'\\' if !is_raw => {
+                    // `\N{name}` is a named character, not a field.
+                    if content[i + 1..].starts_with("N{") {
+                        i += content[i..].find('}').unwrap_or(bytes.len() - i);
+                    } else {
+                        i += 1;
+                    }
+                }
+                b'{' if top.is_none() && bytes.get(i + 1) == Some(&b'{') => i += 1,
+                b'{' => fields.push(Field::Expression { brackets: 0 }),
+                b'}' if top.is_none() && bytes.get(i + 1) == Some(&b'}') => i += 1,
+                b'}' if top.is_none() => {}
+                b'}' => {
+                    fields.pop();
+                }
+                _ => {}
+            },
         }
-        i += ch_len;
+        i += 1;
     }
-    depth > 0
+    !fields.is_empty()
 }
 
 pub fn find_prefix_query(content_before_cursor: &str, config: &Config) -> Option<PathQuery> {
@@ -356,6 +401,31 @@ pub fn resolve_list_dirs(
     }
 }
 
+/// Resolves the full contents of a literal to the paths it may refer to, one
+/// per listing directory `resolve_list_dirs` would use for it.
+pub fn resolve_literal_paths(
+    content: &str,
+    file_dir: Option<&Path>,
+    root_dir: Option<&Path>,
+    config: &Config,
+) -> Vec<PathBuf> {
+    let prefix_kind = prefix_kind_for_path(content, config);
+    let (mut dir_part, mut segment) = split_dir_and_segment(content);
+    if prefix_kind == PrefixKind::Home && dir_part.is_empty() {
+        std::mem::swap(&mut dir_part, &mut segment);
+    }
+    let query = PathQuery {
+        dir_part,
+        segment_prefix: segment,
+        path_str: content.to_string(),
+        prefix_kind,
+    };
+    resolve_list_dirs(&query, file_dir, root_dir, config)
+        .into_iter()
+        .map(|dir| apply_relative_dir(&dir, &query.segment_prefix))
+        .collect()
+}
+
 fn apply_relative_dir(base: &Path, dir_part: &str) -> PathBuf {
     let mut current = base.to_path_buf();
     for part in dir_part.split(&['/', '\\'][..]) {
@@ -373,7 +443,7 @@ fn apply_relative_dir(base: &Path, dir_part: &str) -> PathBuf {
     current
 }
 
-fn dirs_home() -> Option<PathBuf> {
+pub fn dirs_home() -> Option<PathBuf> {
     if let Some(home) = std::env::var_os("HOME") {
         return Some(PathBuf::from(home));
     }
@@ -387,8 +457,12 @@ pub fn filter_entries(
     entries: Vec<(String, bool, PathBuf)>,
     segment_prefix: &str,
     config: &Config,
-) -> Vec<(String, bool)> {
-    let mut filtered: Vec<(String, bool)> = entries
+    gitignore: Option<&GitIgnore>,
+    expectation: &PathExpectation,
+) -> Vec<(String, bool, PathBuf)> {
+    let case_sensitive = is_case_sensitive(segment_prefix, config);
+    let folded_prefix = fold_for_match(segment_prefix, case_sensitive);
+    let mut scored: Vec<(i64, (String, bool, PathBuf))> = entries
         .into_iter()
         .filter(|(name, is_dir, path)| {
             if !config.show_hidden && name.starts_with('.') {
@@ -400,111 +474,151 @@ pub fn filter_entries(
             if !config.include_files && !*is_dir {
                 return false;
             }
-            if !name.starts_with(segment_prefix) {
+            if !expectation.accepts(*is_dir) {
                 return false;
             }
-            let normalized = normalize_for_match(path);
-            !config
-                .ignore_globs
-                .iter()
-                .any(|pattern| glob_match(pattern, &normalized))
+            if gitignore.is_some_and(|rules| rules.is_ignored(path, *is_dir)) {
+                return false;
+            }
+            !config.ignore_set.is_match(path, *is_dir)
+        })
+        .filter_map(|entry| {
+            let score = match config.matching {
+                MatchingStrategy::Prefix => fold_for_match(&entry.0, case_sensitive)
+                    .starts_with(&folded_prefix)
+                    .then_some(0),
+                MatchingStrategy::Fuzzy => fuzzy_score(segment_prefix, &entry.0, case_sensitive),
+            }?;
+            Some((score, entry))
         })
-        .map(|(name, is_dir, _)| (name, is_dir))
         .collect();
-    filtered.sort_by(|(a_name, a_dir), (b_name, b_dir)| {
-        b_dir.cmp(a_dir).then_with(|| a_name.cmp(b_name))
-    });
-    filtered
+    // Files with an expected extension come before other files.
+    scored.sort_by(
+        |(a_score, (a_name, a_dir, _)), (b_score, (b_name, b_dir, _))| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| b_dir.cmp(a_dir))
+                .then_with(|| {
+                    expectation
+                        .prefers(b_name)
+                        .cmp(&expectation.prefers(a_name))
+                })
+                .then_with(|| a_name.cmp(b_name))
+        },
+    );
+    scored.into_iter().map(|(_, entry)| entry).collect()
 }
 
-pub fn normalize_for_match(path: &Path) -> String {
-    path.to_string_lossy().replace('\\', "/")
+const FUZZY_MATCH: i64 = 16;
+const FUZZY_BOUNDARY_BONUS: i64 = 24;
+const FUZZY_CONSECUTIVE_BONUS: i64 = 16;
+const FUZZY_GAP_PENALTY: i64 = 1;
+const FUZZY_PREFIX_BONUS: i64 = 100;
+
+/// Scores `candidate` as a subsequence match of `pattern`, or returns `None`
+/// when some pattern character cannot be matched in order. Matches at word
+/// boundaries (`_`, `-`, `.`, spaces and camelCase humps), consecutive runs and
+/// exact prefixes score higher.
+pub fn fuzzy_score(pattern: &str, candidate: &str, case_sensitive: bool) -> Option<i64> {
+    let pattern_text = fold_for_match(pattern, true);
+    let candidate_text = fold_for_match(candidate, true);
+    let pattern: Vec<char> = pattern_text.chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate_text.chars().collect();
+    if pattern.len() > chars.len() {
+        return None;
+    }
+
+    const NONE: i64 = i64::MIN / 2;
+    // prev[j]: best score with the previous pattern char matched at `j`.
+    let mut prev = vec![NONE; chars.len()];
+    for (i, pc) in pattern.iter().enumerate() {
+        let mut best = vec![NONE; chars.len()];
+        // Best score of the previous pattern char matched anywhere before `j`,
+        // less one gap penalty per skipped character.
+        let mut running = NONE;
+        for j in 0..chars.len() {
+            if j > 0 && i > 0 {
+                running = running.max(prev[j - 1]) - FUZZY_GAP_PENALTY;
+            }
+            if !chars_match(chars[j], *pc, case_sensitive) {
+                continue;
+            }
+            let bonus = FUZZY_MATCH + boundary_bonus(&chars, j);
+            if i == 0 {
+                best[j] = bonus - j as i64 * FUZZY_GAP_PENALTY;
+                continue;
+            }
+            let consecutive = if j > 0 && prev[j - 1] > NONE {
+                prev[j - 1] + FUZZY_CONSECUTIVE_BONUS
+            } else {
+                NONE
+            };
+            let from = consecutive.max(running);
+            if from > NONE {
+                best[j] = from + bonus;
+            }
+        }
+        prev = best;
+    }
+
+    let score = prev.into_iter().max().filter(|s| *s > NONE)?;
+    if fold_for_match(&candidate_text, case_sensitive)
+        .starts_with(&fold_for_match(&pattern_text, case_sensitive))
+    {
+        return Some(score + FUZZY_PREFIX_BONUS);
+    }
+    Some(score)
 }
 
-pub fn glob_match(pattern: &str, text: &str) -> bool {
-    let tokens = tokenize_glob(pattern);
-    let mut memo = std::collections::HashMap::new();
-    glob_match_tokens(&tokens, text.as_bytes(), 0, 0, &mut memo)
+fn chars_match(a: char, b: char, case_sensitive: bool) -> bool {
+    a == b || (!case_sensitive && a.to_lowercase().eq(b.to_lowercase()))
 }
 
-#[derive(Debug, Clone, Copy)]
-enum GlobToken {
-    Char(u8),
-    Star,
-    GlobStar,
+/// Whether matching `query` should respect case under the `match_case`
+/// setting; `smart` turns sensitive as soon as the query has an uppercase
+/// letter.
+pub fn is_case_sensitive(query: &str, config: &Config) -> bool {
+    match config.match_case {
+        MatchCase::Sensitive => true,
+        MatchCase::Insensitive => false,
+        MatchCase::Smart => query.chars().any(char::is_uppercase),
+    }
 }
 
-fn tokenize_glob(pattern: &str) -> Vec<GlobToken> {
-    let mut tokens = Vec::new();
-    let bytes = pattern.as_bytes();
-    let mut i = 0usize;
-    while i < bytes.len() {
-        if bytes[i] == b'*' {
-            if i + 1 < bytes.len() && bytes[i + 1] == b'*' {
-                tokens.push(GlobToken::GlobStar);
-                i += 2;
-                continue;
-            }
-            tokens.push(GlobToken::Star);
-            i += 1;
-            continue;
-        }
-        tokens.push(GlobToken::Char(bytes[i]));
-        i += 1;
+/// Folds text for comparison: NFC-normalized so decomposed names (as created
+/// on macOS) compare equal to composed input, and lowercased when matching
+/// ignores case.
+pub fn fold_for_match(text: &str, case_sensitive: bool) -> String {
+    let normalized: String = if is_nfc(text) {
+        text.to_string()
+    } else {
+        text.nfc().collect()
+    };
+    if case_sensitive {
+        normalized
+    } else {
+        normalized.to_lowercase()
     }
-    tokens
 }
 
-fn glob_match_tokens(
-    tokens: &[GlobToken],
-    text: &[u8],
-    ti: usize,
-    xi: usize,
-    memo: &mut std::collections::HashMap<(usize, usize), bool>,
-) -> bool {
-    if let Some(&cached) = memo.get(&(ti, xi)) {
-        return cached;
+fn boundary_bonus(chars: &[char], idx: usize) -> i64 {
+    if idx == 0 {
+        return FUZZY_BOUNDARY_BONUS;
     }
-    let matched = if ti == tokens.len() {
-        xi == text.len()
+    let prev = chars[idx - 1];
+    let cur = chars[idx];
+    if matches!(prev, '_' | '-' | '.' | ' ') || (prev.is_lowercase() && cur.is_uppercase()) {
+        FUZZY_BOUNDARY_BONUS
     } else {
-        match tokens[ti] {
-            GlobToken::Char(c) => {
-                xi < text.len()
-                    && text[xi] == c
-                    && glob_match_tokens(tokens, text, ti + 1, xi + 1, memo)
-            }
-            GlobToken::Star => {
-                let mut j = xi;
-                let mut ok = false;
-                while j <= text.len() {
-                    if j < text.len() && text[j] == b'/' {
-                        break;
-                    }
-                    if glob_match_tokens(tokens, text, ti + 1, j, memo) {
-                        ok = true;
-                        break;
-                    }
-                    j += 1;
-                }
-                ok
-            }
-            GlobToken::GlobStar => {
-                let mut j = xi;
-                let mut ok = false;
-                while j <= text.len() {
-                    if glob_match_tokens(tokens, text, ti + 1, j, memo) {
-                        ok = true;
-                        break;
-                    }
-                    j += 1;
-                }
-                ok
-            }
-        }
-    };
-    memo.insert((ti, xi), matched);
-    matched
+        0
+    }
+}
+
+pub fn normalize_for_match(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
 }
 
 pub fn is_windows_drive_prefix(s: &str) -> bool {
@@ -521,16 +635,6 @@ pub fn is_windows_drive_prefix(s: &str) -> bool {
     matches!(chars.next(), Some('\\') | Some('/'))
 }
 
-pub fn segment_start_offset(content: &str) -> usize {
-    let mut last_sep = None;
-    for (idx, ch) in content.char_indices() {
-        if ch == '/' || ch == '\\' {
-            last_sep = Some(idx + ch.len_utf8());
-        }
-    }
-    last_sep.unwrap_or(0)
-}
-
 pub fn separator_for_insertion(content_before_cursor: &str, config: &Config) -> char {
     if config.prefer_forward_slashes {
         return '/';
@@ -548,15 +652,61 @@ pub fn utf16_len(text: &str) -> u32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ExpectedKind;
+    use crate::glob::GlobSet;
+    use crate::tokenizer::tokenize_strings;
+
+    fn string_info(text: &str, cursor: usize) -> Option<StringInfo> {
+        find_string_info(text, &tokenize_strings(text), cursor)
+    }
 
     #[test]
     fn detects_string_context_simple() {
         let line = "open(\"./foo\")";
         let cursor = line.find("./foo").unwrap() + "./foo".len();
-        let info = find_string_info(line, cursor).unwrap();
+        let info = string_info(line, cursor).unwrap();
         assert_eq!(info.content_before_cursor, "./foo");
     }
 
+    #[test]
+    fn detects_strings_spanning_lines() {
+        let text = "doc = \"\"\"\nSee data/tr\n\"\"\"\np = open(\"data/\"\n         \"raw/tr\")\n";
+        let in_docstring = text.find("tr\n").unwrap() + 2;
+        let info = string_info(text, in_docstring).unwrap();
+        assert_eq!(info.content_before_cursor, "See data/tr");
+        assert_eq!(info.segment_start_utf16, "See data/".len() as u32);
+
+        let in_concat = text.find("raw/tr").unwrap() + "raw/tr".len();
+        let info = string_info(text, in_concat).unwrap();
+        assert_eq!(info.content_before_cursor, "data/raw/tr");
+        assert_eq!(info.segment_start_utf16, 14);
+        assert_eq!(info.string_start_byte, text.find("\"data/").unwrap());
+    }
+
+    #[test]
+    fn continuation_joins_lines_of_one_string() {
+        let text = "p = open(\"data/\\\nraw/\")\n";
+        let cursor = text.find("raw/").unwrap() + 4;
+        let info = string_info(text, cursor).unwrap();
+        assert_eq!(info.content_before_cursor, "data/raw/");
+        assert_eq!(info.segment_start_utf16, 4);
+    }
+
+    #[test]
+    fn decodes_escapes_before_cursor() {
+        let text = "p = open(\"C:\\\\Users\\\\it\\'s\\\\do\")\n";
+        let cursor = text.find("do\"").unwrap() + 2;
+        let info = string_info(text, cursor).unwrap();
+        assert_eq!(info.content_before_cursor, "C:\\Users\\it's\\do");
+        assert_eq!(info.segment_start_utf16, text.find("do\"").unwrap() as u32);
+
+        let raw = "p = open(r\"C:\\Users\\do\")\n";
+        let cursor = raw.find("do\"").unwrap() + 2;
+        let info = string_info(raw, cursor).unwrap();
+        assert_eq!(info.content_before_cursor, "C:\\Users\\do");
+        assert!(info.kind.is_raw);
+    }
+
     #[test]
     fn detects_path_query_with_segment() {
         let config = Config::default();
@@ -588,11 +738,139 @@ mod tests {
             ("a.txt".to_string(), false, PathBuf::from("/tmp/a.txt")),
         ];
         let config = Config::default();
-        let filtered = filter_entries(entries, "", &config);
+        let filtered = filter_entries(entries, "", &config, None, &PathExpectation::default());
         assert_eq!(filtered[0].0, "a");
         assert!(filtered[0].1);
     }
 
+    #[test]
+    fn ignore_globs_match_directory_entries() {
+        let entries = vec![
+            ("build".to_string(), true, PathBuf::from("/tmp/build")),
+            (
+                "build.py".to_string(),
+                false,
+                PathBuf::from("/tmp/build.py"),
+            ),
+        ];
+        let mut config = Config {
+            ignore_globs: vec!["**/build/**".into()],
+            ..Config::default()
+        };
+        config.ignore_set = GlobSet::new(&config.ignore_globs, &config, None);
+        let filtered = filter_entries(entries, "", &config, None, &PathExpectation::default());
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, "build.py");
+    }
+
+    #[test]
+    fn expected_extensions_rank_first_and_dirs_stay() {
+        let entries: Vec<(String, bool, PathBuf)> =
+            [("a.png", false), ("b.npz", false), ("c", true)]
+                .iter()
+                .map(|(name, is_dir)| (name.to_string(), *is_dir, PathBuf::from("/tmp").join(name)))
+                .collect();
+        let config = Config::default();
+        let arrays = PathExpectation {
+            kind: ExpectedKind::File,
+            extensions: vec!["npz".into()],
+            may_be_missing: false,
+        };
+        let names = |expectation: &PathExpectation| {
+            filter_entries(entries.clone(), "", &config, None, expectation)
+                .into_iter()
+                .map(|(name, _, _)| name)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&arrays), vec!["c", "b.npz", "a.png"]);
+        let dirs = PathExpectation {
+            kind: ExpectedKind::Dir,
+            extensions: Vec::new(),
+            may_be_missing: false,
+        };
+        assert_eq!(names(&dirs), vec!["c"]);
+    }
+
+    #[test]
+    fn fuzzy_matches_word_boundaries() {
+        assert!(fuzzy_score("trcsv", "train_split.csv", true).is_some());
+        assert!(fuzzy_score("trcsv", "data.csv", true).is_none());
+        assert!(fuzzy_score("tr", "train.csv", true) > fuzzy_score("tr", "xtr.csv", true));
+        assert!(fuzzy_score("mc", "my_config.py", true) > fuzzy_score("mc", "mac.py", true));
+        assert!(fuzzy_score("mC", "myConfig.py", true) > fuzzy_score("mC", "mxxC.py", true));
+    }
+
+    #[test]
+    fn fuzzy_mode_ranks_best_match_first() {
+        let entries: Vec<(String, bool, PathBuf)> = ["data.csv", "tests.csv", "train_split.csv"]
+            .iter()
+            .map(|name| (name.to_string(), false, PathBuf::from("/tmp").join(name)))
+            .collect();
+        let config = Config {
+            matching: MatchingStrategy::Fuzzy,
+            ..Config::default()
+        };
+        let filtered = filter_entries(
+            entries.clone(),
+            "trcsv",
+            &config,
+            None,
+            &PathExpectation::default(),
+        );
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, "train_split.csv");
+        let filtered = filter_entries(entries, "ts", &config, None, &PathExpectation::default());
+        assert_eq!(filtered.len(), 3);
+        assert_eq!(filtered[0].0, "train_split.csv");
+    }
+
+    #[test]
+    fn match_case_policies() {
+        let entries: Vec<(String, bool, PathBuf)> = ["Data", "data.csv", "docs"]
+            .iter()
+            .map(|name| (name.to_string(), false, PathBuf::from("/tmp").join(name)))
+            .collect();
+        let names = |prefix: &str, match_case: MatchCase| -> Vec<String> {
+            let config = Config {
+                match_case,
+                ..Config::default()
+            };
+            filter_entries(
+                entries.clone(),
+                prefix,
+                &config,
+                None,
+                &PathExpectation::default(),
+            )
+            .into_iter()
+            .map(|(name, _, _)| name)
+            .collect()
+        };
+        assert_eq!(names("da", MatchCase::Sensitive), vec!["data.csv"]);
+        assert_eq!(
+            names("da", MatchCase::Insensitive),
+            vec!["Data", "data.csv"]
+        );
+        assert_eq!(names("da", MatchCase::Smart), vec!["Data", "data.csv"]);
+        assert_eq!(names("Da", MatchCase::Smart), vec!["Data"]);
+    }
+
+    #[test]
+    fn matches_across_unicode_normalization_forms() {
+        let decomposed = "cafe\u{301}.csv".to_string();
+        let entries = vec![(decomposed.clone(), false, PathBuf::from("/tmp/x"))];
+        let filtered = filter_entries(
+            entries,
+            "caf\u{e9}",
+            &Config::default(),
+            None,
+            &PathExpectation::default(),
+        );
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].0, decomposed);
+        assert!(fuzzy_score("c\u{e9}csv", &decomposed, true).is_some());
+    }
+
     #[test]
     fn detects_windows_drive_prefix() {
         assert!(is_windows_drive_prefix("C:\\Users"));
@@ -600,27 +878,72 @@ mod tests {
         assert!(!is_windows_drive_prefix("/tmp"));
     }
 
-    #[test]
-    fn segment_start_offset_after_separator() {
-        let offset = segment_start_offset("./foo/bar");
-        assert_eq!(offset, "./foo/".len());
-    }
-
     #[test]
     fn ignores_fstring_interpolation() {
         let line = "f\"{value}/data\"";
         let cursor_in_expr = line.find("value").unwrap();
         let cursor_in_text = line.find("data").unwrap();
-        let info_expr = find_string_info(line, cursor_in_expr);
-        let info_text = find_string_info(line, cursor_in_text);
+        let info_expr = string_info(line, cursor_in_expr);
+        let info_text = string_info(line, cursor_in_text);
         assert!(info_expr.is_none());
         assert!(info_text.is_some());
+
+        let line = "f\"data/{{x}}/r\"";
+        let info = string_info(line, line.len() - 1).unwrap();
+        assert_eq!(info.content_before_cursor, "data/{x}/r");
+    }
+
+    #[test]
+    fn prefixed_strings_keep_their_call_context() {
+        let text = "blob = open(rb\"assets/lo\")\nicon = Path(b\"icons/caf\\xc3\\xa9\")\n";
+        let cursor = text.find("lo\"").unwrap() + 2;
+        let info = string_info(text, cursor).unwrap();
+        assert_eq!(info.string_start_byte, text.find("rb").unwrap());
+        assert!(crate::context::is_path_context(
+            text,
+            &tokenize_strings(text),
+            info.string_start_byte,
+            &Config::default(),
+            &[]
+        ));
+
+        let cursor = text.rfind("\")\n").unwrap();
+        let info = string_info(text, cursor).unwrap();
+        assert_eq!(info.content_before_cursor, "icons/café");
+        assert!(info.kind.is_bytes);
+        assert!(string_info("x = ub\"data/\"", 10).is_none());
     }
 
     #[test]
-    fn glob_match_basic() {
-        assert!(glob_match("**/node_modules/**", "/proj/node_modules/pkg"));
-        assert!(glob_match("**/.git/**", "/proj/.git/config"));
-        assert!(!glob_match("**/.venv/**", "/proj/src/main.py"));
+    fn tracks_nested_format_specs() {
+        assert!(!is_in_interpolation("{x:{width}}/", false));
+        assert!(is_in_interpolation("{x:{width}", false));
+        assert!(is_in_interpolation("{x:>", false));
+        assert!(!is_in_interpolation("{d['}']}/{{", false));
+        assert!(!is_in_interpolation("\\N{BULLET} ", false));
+        assert!(is_in_interpolation("\\N{", true));
+    }
+
+    #[test]
+    fn finds_closed_literals_on_line() {
+        let line = "shutil.copy(\"a.txt\", r'b\\c') # \"comment\"";
+        let literals = find_string_literals(line, &tokenize_strings(line));
+        assert_eq!(literals.len(), 2);
+        assert_eq!(literals[0].content, "a.txt");
+        assert_eq!(literals[1].content, "b\\c");
+        assert!(literals[1].kind.is_raw);
+        assert_eq!(
+            &line[literals[1].content_start_byte..literals[1].content_end_byte],
+            "b\\c"
+        );
+    }
+
+    #[test]
+    fn resolves_literal_against_file_dir() {
+        let config = Config::default();
+        let paths = resolve_literal_paths("data/raw", Some(Path::new("/proj")), None, &config);
+        assert_eq!(paths, vec![PathBuf::from("/proj/data/raw")]);
+        let paths = resolve_literal_paths("./", Some(Path::new("/proj")), None, &config);
+        assert_eq!(paths, vec![PathBuf::from("/proj")]);
     }
 }
@@ -1,5 +1,9 @@
+use std::path::Path;
+
 use serde_json::Value;
 
+use crate::glob::GlobSet;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ContextGating {
     Off,
@@ -27,6 +31,56 @@ pub enum StatStrategy {
     Eager,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchingStrategy {
+    Prefix,
+    Fuzzy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchCase {
+    Sensitive,
+    Insensitive,
+    Smart,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticsSeverity {
+    Off,
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// What a path argument is expected to name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExpectedKind {
+    #[default]
+    Any,
+    File,
+    Dir,
+}
+
+/// An argument of a call, by position or by keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgSlot {
+    Position(usize),
+    Keyword(String),
+}
+
+/// A user-declared path API from the `path_functions` setting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathFunctionRule {
+    /// Dotted name of the function; calls through a trailing part of the
+    /// name, as after `from mylib import io`, match too.
+    pub call: String,
+    pub args: Vec<ArgSlot>,
+    pub kind: ExpectedKind,
+    /// Lowercase extensions without the leading dot.
+    pub extensions: Vec<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub enable: bool,
@@ -40,6 +94,8 @@ pub struct Config {
     pub include_directories: bool,
     pub directory_trailing_slash: bool,
     pub ignore_globs: Vec<String>,
+    /// `ignore_globs` compiled against the workspace root by `load_config`.
+    pub ignore_set: GlobSet,
     pub prefer_forward_slashes: bool,
     pub expand_tilde: bool,
     pub windows_enable_drive_prefix: bool,
@@ -47,11 +103,23 @@ pub struct Config {
     pub cache_ttl_ms: u64,
     pub cache_max_dirs: usize,
     pub stat_strategy: StatStrategy,
+    pub diagnostics_severity: DiagnosticsSeverity,
+    pub diagnostics_debounce_ms: u64,
+    pub preview_lines: usize,
+    pub matching: MatchingStrategy,
+    pub match_case: MatchCase,
+    pub workspace_index: bool,
+    pub workspace_index_max_files: usize,
+    pub respect_gitignore: bool,
+    pub path_functions: Vec<PathFunctionRule>,
+    /// Directory notebooks resolve relative paths from, as the kernel's
+    /// working directory; `None` uses the notebook's own directory.
+    pub notebook_working_dir: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
-        Self {
+        let config = Self {
             enable: true,
             path_prefix_fallback: true,
             context_gating: ContextGating::Smart,
@@ -79,11 +147,23 @@ impl Default for Config {
             cache_ttl_ms: 500,
             cache_max_dirs: 64,
             stat_strategy: StatStrategy::Lazy,
-        }
+            diagnostics_severity: DiagnosticsSeverity::Warning,
+            diagnostics_debounce_ms: 300,
+            preview_lines: 10,
+            matching: MatchingStrategy::Prefix,
+            match_case: MatchCase::Sensitive,
+            workspace_index: false,
+            workspace_index_max_files: 50_000,
+            respect_gitignore: false,
+            path_functions: Vec::new(),
+            notebook_working_dir: None,
+            ignore_set: GlobSet::default(),
+        };
+        with_ignore_set(config, None)
     }
 }
 
-pub fn select_settings_root<'a>(value: &'a Value) -> Option<&'a Value> {
+pub fn select_settings_root(value: &Value) -> Option<&Value> {
     let mut current = value;
     if let Some(settings) = current.get("settings") {
         current = settings;
@@ -102,13 +182,13 @@ pub fn select_settings_root<'a>(value: &'a Value) -> Option<&'a Value> {
     current.as_object().map(|_| current)
 }
 
-pub fn load_config(value: &Value, warned: &mut bool) -> Config {
+pub fn load_config(value: &Value, warned: &mut bool, root_dir: Option<&Path>) -> Config {
     let mut config = Config::default();
     let Some(root) = select_settings_root(value) else {
-        return config;
+        return with_ignore_set(config, root_dir);
     };
     let Some(map) = root.as_object() else {
-        return config;
+        return with_ignore_set(config, root_dir);
     };
 
     let mut warnings = Vec::new();
@@ -130,7 +210,7 @@ pub fn load_config(value: &Value, warned: &mut bool) -> Config {
                         }
                     };
                 } else {
-                    warnings.push(format!("invalid context_gating type"));
+                    warnings.push("invalid context_gating type".into());
                 }
             }
             "base_dir" => {
@@ -145,7 +225,7 @@ pub fn load_config(value: &Value, warned: &mut bool) -> Config {
                         }
                     };
                 } else {
-                    warnings.push(format!("invalid base_dir type"));
+                    warnings.push("invalid base_dir type".into());
                 }
             }
             "workspace_root_strategy" => {
@@ -159,7 +239,7 @@ pub fn load_config(value: &Value, warned: &mut bool) -> Config {
                         }
                     };
                 } else {
-                    warnings.push(format!("invalid workspace_root_strategy type"));
+                    warnings.push("invalid workspace_root_strategy type".into());
                 }
             }
             "max_results" => set_usize(&mut config.max_results, val, key, &mut warnings),
@@ -221,6 +301,87 @@ pub fn load_config(value: &Value, warned: &mut bool) -> Config {
                     warnings.push("invalid stat_strategy type".into());
                 }
             }
+            "diagnostics_severity" => {
+                if let Some(s) = val.as_str() {
+                    config.diagnostics_severity = match s {
+                        "off" => DiagnosticsSeverity::Off,
+                        "error" => DiagnosticsSeverity::Error,
+                        "warning" => DiagnosticsSeverity::Warning,
+                        "information" => DiagnosticsSeverity::Information,
+                        "hint" => DiagnosticsSeverity::Hint,
+                        _ => {
+                            warnings.push(format!("invalid diagnostics_severity: {s}"));
+                            config.diagnostics_severity
+                        }
+                    };
+                } else {
+                    warnings.push("invalid diagnostics_severity type".into());
+                }
+            }
+            "diagnostics_debounce_ms" => {
+                set_u64(&mut config.diagnostics_debounce_ms, val, key, &mut warnings)
+            }
+            "preview_lines" => set_usize(&mut config.preview_lines, val, key, &mut warnings),
+            "matching" => {
+                if let Some(s) = val.as_str() {
+                    config.matching = match s {
+                        "prefix" => MatchingStrategy::Prefix,
+                        "fuzzy" => MatchingStrategy::Fuzzy,
+                        _ => {
+                            warnings.push(format!("invalid matching: {s}"));
+                            config.matching
+                        }
+                    };
+                } else {
+                    warnings.push("invalid matching type".into());
+                }
+            }
+            "match_case" => {
+                if let Some(s) = val.as_str() {
+                    config.match_case = match s {
+                        "sensitive" => MatchCase::Sensitive,
+                        "insensitive" => MatchCase::Insensitive,
+                        "smart" => MatchCase::Smart,
+                        _ => {
+                            warnings.push(format!("invalid match_case: {s}"));
+                            config.match_case
+                        }
+                    };
+                } else {
+                    warnings.push("invalid match_case type".into());
+                }
+            }
+            "respect_gitignore" => set_bool(&mut config.respect_gitignore, val, key, &mut warnings),
+            "workspace_index" => set_bool(&mut config.workspace_index, val, key, &mut warnings),
+            "workspace_index_max_files" => set_usize(
+                &mut config.workspace_index_max_files,
+                val,
+                key,
+                &mut warnings,
+            ),
+            "path_functions" => {
+                if let Some(list) = val.as_array() {
+                    config.path_functions = list
+                        .iter()
+                        .filter_map(|entry| {
+                            let rule = parse_path_function(entry);
+                            if rule.is_none() {
+                                warnings.push("invalid path_functions entry".into());
+                            }
+                            rule
+                        })
+                        .collect();
+                } else {
+                    warnings.push("invalid path_functions type".into());
+                }
+            }
+            "notebook_working_dir" => match val {
+                Value::String(dir) => {
+                    config.notebook_working_dir = (!dir.is_empty()).then(|| dir.clone());
+                }
+                Value::Null => config.notebook_working_dir = None,
+                _ => warnings.push("invalid notebook_working_dir type".into()),
+            },
             _ => {}
         }
     }
@@ -230,9 +391,62 @@ pub fn load_config(value: &Value, warned: &mut bool) -> Config {
         *warned = true;
     }
 
+    with_ignore_set(config, root_dir)
+}
+
+/// Compiles `ignore_globs` for every configuration `load_config` returns, so
+/// the initial settings and each reload in `apply_config_update` share one
+/// code path. The workspace root the set is anchored to comes from the client
+/// rather than from the settings, so it is already known at this point.
+fn with_ignore_set(mut config: Config, root_dir: Option<&Path>) -> Config {
+    config.ignore_set = GlobSet::new(&config.ignore_globs, &config, root_dir);
     config
 }
 
+/// Parses one `path_functions` rule. `args` defaults to the first positional
+/// argument and `kind` to `"any"`.
+fn parse_path_function(value: &Value) -> Option<PathFunctionRule> {
+    let call = value.get("call")?.as_str()?.trim();
+    if call.is_empty() {
+        return None;
+    }
+    let args = match value.get("args") {
+        None => vec![ArgSlot::Position(0)],
+        Some(args) => args
+            .as_array()?
+            .iter()
+            .map(|arg| match arg {
+                Value::Number(n) => Some(ArgSlot::Position(n.as_u64()? as usize)),
+                Value::String(s) => Some(ArgSlot::Keyword(s.clone())),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?,
+    };
+    let kind = match value.get("kind") {
+        None => ExpectedKind::Any,
+        Some(kind) => match kind.as_str()? {
+            "any" => ExpectedKind::Any,
+            "file" => ExpectedKind::File,
+            "dir" | "directory" => ExpectedKind::Dir,
+            _ => return None,
+        },
+    };
+    let extensions = match value.get("extensions") {
+        None => Vec::new(),
+        Some(extensions) => extensions
+            .as_array()?
+            .iter()
+            .map(|ext| Some(ext.as_str()?.trim_start_matches('.').to_lowercase()))
+            .collect::<Option<Vec<_>>>()?,
+    };
+    Some(PathFunctionRule {
+        call: call.to_string(),
+        args,
+        kind,
+        extensions,
+    })
+}
+
 fn set_bool(target: &mut bool, value: &Value, key: &str, warnings: &mut Vec<String>) {
     if let Some(v) = value.as_bool() {
         *target = v;
@@ -265,7 +479,7 @@ mod tests {
     #[test]
     fn loads_defaults_when_missing() {
         let mut warned = false;
-        let cfg = load_config(&json!({}), &mut warned);
+        let cfg = load_config(&json!({}), &mut warned, None);
         assert!(cfg.enable);
         assert_eq!(cfg.max_results, 80);
     }
@@ -278,14 +492,17 @@ mod tests {
                 "enable": false,
                 "max_results": 20,
                 "context_gating": "strict",
-                "ignore_globs": ["**/.git/**"]
+                "ignore_globs": ["**/.git/**"],
+                "notebook_working_dir": "experiments"
             }),
             &mut warned,
+            None,
         );
         assert!(!cfg.enable);
         assert_eq!(cfg.max_results, 20);
         assert_eq!(cfg.context_gating, ContextGating::Strict);
         assert_eq!(cfg.ignore_globs.len(), 1);
+        assert_eq!(cfg.notebook_working_dir.as_deref(), Some("experiments"));
     }
 
     #[test]
@@ -304,7 +521,63 @@ mod tests {
                 }
             }),
             &mut warned,
+            None,
         );
         assert!(cfg.show_hidden);
     }
+
+    #[test]
+    fn parses_diagnostics_settings() {
+        let mut warned = false;
+        let cfg = load_config(
+            &json!({
+                "diagnostics_severity": "error",
+                "diagnostics_debounce_ms": 50
+            }),
+            &mut warned,
+            None,
+        );
+        assert_eq!(cfg.diagnostics_severity, DiagnosticsSeverity::Error);
+        assert_eq!(cfg.diagnostics_debounce_ms, 50);
+    }
+
+    #[test]
+    fn parses_path_function_rules() {
+        let mut warned = false;
+        let cfg = load_config(
+            &json!({
+                "path_functions": [
+                    {
+                        "call": "mylib.io.load",
+                        "args": [0, "src"],
+                        "kind": "file",
+                        "extensions": [".NPZ"]
+                    },
+                    {"call": "mylib.io.scan"},
+                    {"call": "mylib.io.bad", "kind": "socket"},
+                    {"args": [0]}
+                ]
+            }),
+            &mut warned,
+            None,
+        );
+        assert_eq!(
+            cfg.path_functions,
+            vec![
+                PathFunctionRule {
+                    call: "mylib.io.load".into(),
+                    args: vec![ArgSlot::Position(0), ArgSlot::Keyword("src".into())],
+                    kind: ExpectedKind::File,
+                    extensions: vec!["npz".into()],
+                },
+                PathFunctionRule {
+                    call: "mylib.io.scan".into(),
+                    args: vec![ArgSlot::Position(0)],
+                    kind: ExpectedKind::Any,
+                    extensions: Vec::new(),
+                },
+            ]
+        );
+        assert!(warned);
+    }
 }
@@ -1,35 +1,27 @@
+use crate::config::{ArgSlot, Config, ExpectedKind, PathFunctionRule};
+use crate::eval::{is_join_function, is_path_class};
+use crate::tokenizer::{code_before, string_containing, tokenize_strings, StringToken};
+
 #[derive(Debug, Clone)]
 pub struct CallContext {
     pub full_name: String,
-    pub base_name: String,
-    pub arg_is_first: bool,
+    /// Document offset where the called name starts.
+    pub name_start: usize,
+    /// Zero-based position of the argument the string is in.
+    pub arg_index: usize,
     pub named_arg: Option<String>,
 }
 
-pub fn detect_call_context(text: &str, string_start_offset: usize) -> Option<CallContext> {
-    let window_start = string_start_offset.saturating_sub(300);
-    let window = &text[window_start..string_start_offset];
-    let mut depth = 0i32;
-    let mut open_idx = None;
-
-    let indices: Vec<(usize, char)> = window.char_indices().collect();
-    for (idx, ch) in indices.iter().rev() {
-        match ch {
-            ')' => depth += 1,
-            '(' => {
-                if depth == 0 {
-                    open_idx = Some(*idx);
-                    break;
-                }
-                depth -= 1;
-            }
-            _ => {}
-        }
+pub fn detect_call_context(
+    text: &str,
+    tokens: &[StringToken],
+    string_start_offset: usize,
+) -> Option<CallContext> {
+    let bracket = enclosing_bracket(text, tokens, string_start_offset)?;
+    if text.as_bytes()[bracket.open] != b'(' {
+        return None;
     }
-
-    let open_idx = open_idx?;
-    let before = window[..open_idx].trim_end();
-    let arg_text = window[open_idx + 1..].to_string();
+    let before = text[..bracket.open].trim_end();
 
     let name_start = before
         .char_indices()
@@ -41,96 +33,900 @@ pub fn detect_call_context(text: &str, string_start_offset: usize) -> Option<Cal
     if full_name.is_empty() {
         return None;
     }
-    let base_name = full_name
-        .rsplit('.')
-        .next()
-        .unwrap_or(&full_name)
-        .to_string();
-
-    let arg_info = analyze_arg_text(&arg_text);
 
     Some(CallContext {
         full_name,
-        base_name,
-        arg_is_first: arg_info.0,
-        named_arg: arg_info.1,
+        name_start,
+        arg_index: bracket.arg_index,
+        named_arg: keyword_before(text, tokens, bracket.arg_start, string_start_offset),
     })
 }
 
-pub fn is_path_context(text: &str, string_start_offset: usize) -> bool {
-    if let Some(ctx) = detect_call_context(text, string_start_offset) {
-        if !ctx.arg_is_first && ctx.named_arg.is_none() {
-            return false;
+/// The innermost `(`, `[` or `{` left open before an offset.
+struct Bracket {
+    open: usize,
+    /// Zero-based position of the item the offset is in.
+    arg_index: usize,
+    /// Where that item starts: after the `(` or the last separating comma.
+    arg_start: usize,
+}
+
+/// How many lines the bracket walk looks back before giving up, so a literal
+/// deep in a long body does not rescan everything above it.
+const MAX_BRACKET_LINES: usize = 200;
+
+/// Finds the bracket enclosing `offset` by walking the code before it,
+/// stepping over strings and comments. The walk gives up at a line that can
+/// only start a statement, so it stays within the current one.
+fn enclosing_bracket(text: &str, tokens: &[StringToken], offset: usize) -> Option<Bracket> {
+    let mut depth = 0usize;
+    let mut arg_index = 0usize;
+    let mut arg_start = None;
+    let mut lines = 0usize;
+    for (idx, byte) in code_before(text, tokens, offset) {
+        match byte {
+            b')' | b']' | b'}' => depth += 1,
+            b'(' | b'[' | b'{' if depth == 0 => {
+                return Some(Bracket {
+                    open: idx,
+                    arg_index,
+                    arg_start: arg_start.unwrap_or(idx + 1),
+                });
+            }
+            b'(' | b'[' | b'{' => depth -= 1,
+            b',' if depth == 0 => {
+                arg_start.get_or_insert(idx + 1);
+                arg_index += 1;
+            }
+            b'\n' => {
+                lines += 1;
+                if lines > MAX_BRACKET_LINES || starts_statement(text, tokens, idx + 1) {
+                    return None;
+                }
+            }
+            _ => {}
         }
+    }
+    None
+}
+
+/// Keywords that only ever begin a statement, so a line starting with one is
+/// never inside an open bracket.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "def", "class", "return", "import", "with", "while", "try", "except", "finally", "elif",
+    "raise", "del", "assert", "pass", "global", "nonlocal",
+];
 
-        if matches_known_path_function(&ctx.full_name, &ctx.base_name) {
-            return true;
+/// Whether the line at `line_start` begins with a statement keyword. Other
+/// lines, even at column 0, may still be arguments of an open bracket.
+fn starts_statement(text: &str, tokens: &[StringToken], line_start: usize) -> bool {
+    let line = text[line_start..].lines().next().unwrap_or("");
+    let code = line.trim_start();
+    if string_containing(tokens, line_start + line.len() - code.len()).is_some() {
+        return false;
+    }
+    let first_word = code
+        .split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .next()
+        .unwrap_or("");
+    STATEMENT_KEYWORDS.contains(&first_word)
+}
+
+/// The keyword the item between `arg_start` and `offset` is passed to, as
+/// `src` in `load(x, src="...`.
+fn keyword_before(
+    text: &str,
+    tokens: &[StringToken],
+    arg_start: usize,
+    offset: usize,
+) -> Option<String> {
+    let mut code: Vec<u8> = code_before(text, tokens, offset)
+        .take_while(|(idx, _)| *idx >= arg_start)
+        .map(|(_, byte)| byte)
+        .filter(|byte| !byte.is_ascii_whitespace() && *byte != b'\\')
+        .collect();
+    code.reverse();
+    let code = String::from_utf8(code).ok()?;
+    let name = code.strip_suffix('=')?;
+    (!name.is_empty() && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_'))
+        .then(|| name.to_string())
+}
+
+/// What the argument a string sits in is expected to name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PathExpectation {
+    pub kind: ExpectedKind,
+    /// Lowercase extensions without the leading dot; empty accepts any.
+    pub extensions: Vec<String>,
+    /// Whether the path need not exist: the call creates it, or only the name
+    /// it is bound to suggests a path, as with `output_dir = "..."`.
+    pub may_be_missing: bool,
+}
+
+impl PathExpectation {
+    /// Whether an entry can be offered at all. Directories stay so that the
+    /// completion can descend into them.
+    pub fn accepts(&self, is_dir: bool) -> bool {
+        is_dir || self.kind != ExpectedKind::Dir
+    }
+
+    /// Whether a file name has one of the expected extensions.
+    pub fn prefers(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.extensions.iter().any(|ext| {
+            name.strip_suffix(ext.as_str())
+                .is_some_and(|stem| stem.len() > 1 && stem.ends_with('.'))
+        })
+    }
+}
+
+pub fn is_path_context(
+    text: &str,
+    tokens: &[StringToken],
+    string_start_offset: usize,
+    config: &Config,
+    signatures: &[PathFunctionRule],
+) -> bool {
+    path_expectation(text, tokens, string_start_offset, config, signatures).is_some()
+}
+
+/// Returns what the string starting at `string_start_offset` should name, or
+/// `None` when it is not in a path context. `signatures` are the workspace
+/// functions the document can call, as resolved by `SignatureCache`.
+pub fn path_expectation(
+    text: &str,
+    tokens: &[StringToken],
+    string_start_offset: usize,
+    config: &Config,
+    signatures: &[PathFunctionRule],
+) -> Option<PathExpectation> {
+    if let Some(ctx) = detect_call_context(text, tokens, string_start_offset) {
+        let rule = matching_rule(&ctx, &config.path_functions).or_else(|| {
+            signatures
+                .iter()
+                .find(|rule| rule.call == ctx.full_name && takes_argument(rule, &ctx))
+        });
+        if let Some(rule) = rule {
+            return Some(PathExpectation {
+                kind: rule.kind,
+                extensions: rule.extensions.clone(),
+                may_be_missing: false,
+            });
+        }
+
+        if let Some(api) = catalogued_api(&ctx) {
+            if api.is_path_class() && ctx.arg_index == 0 {
+                let method = chained_method(&text[string_start_offset..]);
+                // `Path("data/").iterdir()` and friends list a directory.
+                if method.is_some_and(|m| DIRECTORY_METHODS.contains(&m)) {
+                    return Some(PathExpectation {
+                        kind: ExpectedKind::Dir,
+                        extensions: Vec::new(),
+                        may_be_missing: false,
+                    });
+                }
+                if method.is_some_and(|m| EXISTENCE_METHODS.contains(&m)) {
+                    return Some(PathExpectation {
+                        may_be_missing: true,
+                        ..api.expectation(&ctx)
+                    });
+                }
+            }
+            return Some(api.expectation(&ctx));
+        }
+
+        // `data_dir: str = field(default="...")` and pydantic's `Field("...")`.
+        if is_field_default(&ctx) {
+            if let Some(expectation) = binding_expectation(text, ctx.name_start) {
+                return Some(expectation);
+            }
         }
 
         if let Some(name) = ctx.named_arg.as_deref() {
             if matches_named_path_arg(name) {
-                return true;
+                return Some(PathExpectation::default());
+            }
+            if let Some(expectation) = name_expectation(name) {
+                return Some(expectation);
             }
         }
+
+        // Later arguments of path builders such as `os.path.join` only count
+        // when the arguments before them evaluate; see `eval::literal_base`.
+        if ctx.arg_index > 0 && ctx.named_arg.is_none() {
+            return None;
+        }
+    }
+
+    if let Some(expectation) = binding_expectation(text, string_start_offset) {
+        return Some(expectation);
     }
 
-    if path_join_operator_context(text, string_start_offset) {
-        return true;
+    if let Some(open) = enclosing_container(text, tokens, string_start_offset) {
+        if let Some(expectation) = binding_expectation(text, open) {
+            return Some(expectation);
+        }
+        if literal_text(text, string_start_offset).is_some_and(looks_like_path) {
+            return Some(PathExpectation {
+                may_be_missing: true,
+                ..PathExpectation::default()
+            });
+        }
     }
 
-    false
+    path_join_operator_context(text, tokens, string_start_offset).then(PathExpectation::default)
+}
+
+/// Name suffixes that mark a variable, keyword or dict key as a path, with
+/// their plural forms, which name containers of paths.
+const PATH_NAME_SUFFIXES: &[(&str, &str, ExpectedKind)] = &[
+    ("_path", "_paths", ExpectedKind::Any),
+    ("_dir", "_dirs", ExpectedKind::Dir),
+    ("_file", "_files", ExpectedKind::File),
+    ("_root", "_roots", ExpectedKind::Dir),
+    ("_glob", "_globs", ExpectedKind::Any),
+];
+
+/// What a value bound to `name` is expected to name, judging by its suffix.
+fn name_expectation(name: &str) -> Option<PathExpectation> {
+    let name = name.rsplit('.').next().unwrap_or(name).to_lowercase();
+    let (_, _, kind) = PATH_NAME_SUFFIXES
+        .iter()
+        .find(|(singular, plural, _)| name.ends_with(singular) || name.ends_with(plural))?;
+    Some(PathExpectation {
+        kind: *kind,
+        extensions: Vec::new(),
+        may_be_missing: true,
+    })
 }
 
-fn analyze_arg_text(arg_text: &str) -> (bool, Option<String>) {
-    let trimmed = arg_text.trim();
-    if trimmed.is_empty() {
-        return (true, None);
+/// The expectation for a value at `offset` from what it is bound to: an
+/// assignment target or keyword (`train_path = `), possibly annotated as a
+/// path (`output: Path = `), or a dict key (`"log_dir": `).
+fn binding_expectation(text: &str, offset: usize) -> Option<PathExpectation> {
+    let before = text[..offset].trim_end();
+    if let Some(key) = before.strip_suffix(':') {
+        return name_expectation(quoted_text_ending(key.trim_end())?);
     }
-    if trimmed.contains(',') {
-        return (false, None);
+    let target = before.strip_suffix('=')?;
+    if target.ends_with([
+        '=', '!', '<', '>', '+', '-', '*', '/', '%', '&', '|', '^', '@', ':',
+    ]) {
+        return None;
     }
-    if let Some(eq_pos) = trimmed.rfind('=') {
-        let name = trimmed[..eq_pos].trim();
-        if !name.is_empty() {
-            return (false, Some(name.to_string()));
+    let target = target.trim_end();
+    let line = &target[target.rfind('\n').map_or(0, |nl| nl + 1)..];
+    if let Some((name, annotation)) = annotated_target(line) {
+        if is_path_annotation(annotation) {
+            return Some(PathExpectation {
+                may_be_missing: true,
+                ..PathExpectation::default()
+            });
         }
+        return name_expectation(name);
     }
-    (false, None)
+    let name_start = line
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| !is_name_char(*ch))
+        .map_or(0, |(idx, ch)| idx + ch.len_utf8());
+    name_expectation(&line[name_start..])
 }
 
-fn is_name_char(ch: char) -> bool {
-    ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'
+/// Splits `name: annotation`, the part of an annotated assignment before `=`.
+fn annotated_target(line: &str) -> Option<(&str, &str)> {
+    let (name, annotation) = line.trim_start().split_once(':')?;
+    let name = name.trim_end();
+    let is_name = !name.is_empty()
+        && !name.starts_with(|ch: char| ch.is_ascii_digit())
+        && name.chars().all(is_name_char);
+    (is_name && !is_keyword(name)).then_some((name, annotation.trim()))
+}
+
+fn is_keyword(name: &str) -> bool {
+    matches!(
+        name,
+        "else" | "try" | "finally" | "lambda" | "if" | "elif" | "while" | "for" | "with" | "case"
+    )
+}
+
+pub fn is_path_annotation(annotation: &str) -> bool {
+    annotation
+        .split(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+        .any(|word| matches!(word, "Path" | "PurePath" | "PathLike" | "StrPath"))
+}
+
+/// Whether the call context is the default of a dataclass or pydantic field.
+fn is_field_default(ctx: &CallContext) -> bool {
+    let is_field = matches!(
+        ctx.full_name.as_str(),
+        "field" | "dataclasses.field" | "Field" | "pydantic.Field"
+    );
+    is_field
+        && match ctx.named_arg.as_deref() {
+            Some(name) => name == "default",
+            None => ctx.full_name.ends_with("Field") && ctx.arg_index == 0,
+        }
+}
+
+/// Returns the offset of the list, tuple, set or dict literal a value at
+/// `offset` sits in directly.
+fn enclosing_container(text: &str, tokens: &[StringToken], offset: usize) -> Option<usize> {
+    let open = enclosing_bracket(text, tokens, offset)?.open;
+    let before = text[..open].trim_end();
+    // `name[...]` and `name(...)` are subscripts and calls, not literals.
+    let follows_value = before.ends_with(|ch: char| is_name_char(ch) || ch == ')' || ch == ']')
+        && !ends_with_keyword(before);
+    (!follows_value || text.as_bytes()[open] == b'{').then_some(open)
+}
+
+/// Whether `text` ends with a keyword that may precede a literal, as in
+/// `return [`, `in (` or `yield {`.
+fn ends_with_keyword(text: &str) -> bool {
+    let word_start = text
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| !(ch.is_alphanumeric() || *ch == '_'))
+        .map_or(0, |(idx, ch)| idx + ch.len_utf8());
+    matches!(
+        &text[word_start..],
+        "return" | "in" | "yield" | "else" | "and" | "or" | "not" | "await" | "lambda"
+    )
 }
 
-fn matches_known_path_function(full: &str, base: &str) -> bool {
-    matches!(base, "open" | "Path")
-        || matches!(
-            base,
-            "read_csv" | "read_parquet" | "read_json" | "read_excel" | "read_table"
-        )
-        || full.ends_with(".read_csv")
-        || full.ends_with(".read_parquet")
-        || full.ends_with(".read_json")
-        || full.ends_with(".read_excel")
-        || full.ends_with(".read_table")
-        || full.ends_with(".Path")
+/// The contents of the simple quoted string `text` ends with.
+fn quoted_text_ending(text: &str) -> Option<&str> {
+    let quote = text.chars().last().filter(|ch| *ch == '"' || *ch == '\'')?;
+    let body = &text[..text.len() - 1];
+    let start = body.rfind(quote)?;
+    Some(&body[start + 1..])
+}
+
+/// The contents of the string starting at `offset`, up to its closing quote
+/// or the end of the line.
+fn literal_text(text: &str, offset: usize) -> Option<&str> {
+    let rest = &text[offset..];
+    let quote_idx = rest.find(['"', '\''])?;
+    let quote = rest[quote_idx..].chars().next()?;
+    let body = &rest[quote_idx + 1..];
+    let end = body.find([quote, '\n']).unwrap_or(body.len());
+    Some(&body[..end])
+}
+
+/// Whether a container value reads as a path: a directory ending in `/` or a
+/// file with an extension under some directory, as in `"data/train.csv"`.
+/// Names without letters, as in `"3/4.0"`, are numbers rather than paths.
+fn looks_like_path(value: &str) -> bool {
+    if value.contains(char::is_whitespace) || value.contains("://") || !value.contains('/') {
+        return false;
+    }
+    if value.ends_with('/') {
+        return value.contains(char::is_alphabetic);
+    }
+    let name = value.rsplit('/').next().unwrap_or(value);
+    name.rsplit_once('.').is_some_and(|(stem, ext)| {
+        !stem.is_empty()
+            && !ext.is_empty()
+            && ext.chars().all(|ch| ch.is_ascii_alphanumeric())
+            && name.contains(char::is_alphabetic)
+    })
+}
+
+/// Finds the `path_functions` rule declaring the argument a call is at.
+fn matching_rule<'a>(
+    ctx: &CallContext,
+    rules: &'a [PathFunctionRule],
+) -> Option<&'a PathFunctionRule> {
+    rules.iter().find(|rule| {
+        let called = rule.call == ctx.full_name || ends_with_part(&rule.call, &ctx.full_name);
+        called && takes_argument(rule, ctx)
+    })
+}
+
+/// Whether a rule declares the argument a call context is at.
+fn takes_argument(rule: &PathFunctionRule, ctx: &CallContext) -> bool {
+    rule.args.iter().any(|slot| match (slot, &ctx.named_arg) {
+        (ArgSlot::Keyword(name), Some(named)) => name == named,
+        (ArgSlot::Position(index), None) => *index == ctx.arg_index,
+        _ => false,
+    })
+}
+
+/// A library call that takes paths, with the arguments that do.
+struct PathApi {
+    /// Dotted name as usually imported. A leading `*.` matches the name
+    /// called on any receiver or bare, as for methods and re-exports.
+    call: &'static str,
+    positions: &'static [usize],
+    keywords: &'static [&'static str],
+    /// Keywords whose argument expects its own kind instead of `kind`.
+    keyword_kinds: &'static [(&'static str, ExpectedKind)],
+    kind: ExpectedKind,
+    extensions: &'static [&'static str],
+    /// Arguments naming a path the call creates, which need not exist.
+    created_positions: &'static [usize],
+    created_keywords: &'static [&'static str],
+}
+
+impl PathApi {
+    const fn expects(self, kind: ExpectedKind, extensions: &'static [&'static str]) -> Self {
+        PathApi {
+            kind,
+            extensions,
+            ..self
+        }
+    }
+
+    const fn keyword_kinds(self, keyword_kinds: &'static [(&'static str, ExpectedKind)]) -> Self {
+        PathApi {
+            keyword_kinds,
+            ..self
+        }
+    }
+
+    const fn creates(
+        self,
+        created_positions: &'static [usize],
+        created_keywords: &'static [&'static str],
+    ) -> Self {
+        PathApi {
+            created_positions,
+            created_keywords,
+            ..self
+        }
+    }
+
+    /// The `pathlib` classes, whose instances are used through methods.
+    fn is_path_class(&self) -> bool {
+        self.call.starts_with("*.") && self.call.ends_with("Path")
+    }
+
+    /// Whether the call context is at one of the path arguments.
+    fn takes_argument(&self, ctx: &CallContext) -> bool {
+        match &ctx.named_arg {
+            Some(named) => {
+                self.keywords.contains(&named.as_str()) || self.keyword_kind(named).is_some()
+            }
+            None => self.positions.contains(&ctx.arg_index),
+        }
+    }
+
+    fn keyword_kind(&self, named: &str) -> Option<ExpectedKind> {
+        self.keyword_kinds
+            .iter()
+            .find(|(keyword, _)| *keyword == named)
+            .map(|(_, kind)| *kind)
+    }
+
+    fn expectation(&self, ctx: &CallContext) -> PathExpectation {
+        let creates = match &ctx.named_arg {
+            Some(named) => self.created_keywords.contains(&named.as_str()),
+            None => self.created_positions.contains(&ctx.arg_index),
+        };
+        let kind = ctx
+            .named_arg
+            .as_deref()
+            .and_then(|named| self.keyword_kind(named));
+        PathExpectation {
+            kind: kind.unwrap_or(self.kind),
+            extensions: self.extensions.iter().map(|ext| ext.to_string()).collect(),
+            may_be_missing: creates,
+        }
+    }
+}
+
+const fn api(
+    call: &'static str,
+    positions: &'static [usize],
+    keywords: &'static [&'static str],
+) -> PathApi {
+    PathApi {
+        call,
+        positions,
+        keywords,
+        keyword_kinds: &[],
+        kind: ExpectedKind::Any,
+        extensions: &[],
+        created_positions: &[],
+        created_keywords: &[],
+    }
+}
+
+const PANDAS_READ_KEYWORDS: &[&str] = &[
+    "filepath_or_buffer",
+    "path_or_buf",
+    "path_or_buffer",
+    "path",
+    "io",
+];
+const PANDAS_WRITE_KEYWORDS: &[&str] = &["path_or_buf", "path", "excel_writer", "fname"];
+const SUBPROCESS_KEYWORDS: &[(&str, ExpectedKind)] = &[("cwd", DIR), ("executable", FILE)];
+
+const DIR: ExpectedKind = ExpectedKind::Dir;
+const FILE: ExpectedKind = ExpectedKind::File;
+const CSV: &[&str] = &["csv", "tsv", "csv.gz", "tsv.gz", "csv.zip", "csv.bz2"];
+const TEXT_TABLE: &[&str] = &["txt", "tsv", "csv", "dat"];
+const JSON: &[&str] = &["json", "jsonl", "json.gz"];
+const EXCEL: &[&str] = &["xlsx", "xls", "xlsm", "ods"];
+const PICKLE: &[&str] = &["pkl", "pickle", "pkl.gz"];
+const HDF: &[&str] = &["h5", "hdf5", "hdf"];
+const YAML: &[&str] = &["yaml", "yml"];
+const IMAGES: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff", "webp"];
+const FIGURES: &[&str] = &["png", "pdf", "svg", "jpg", "jpeg", "eps"];
+const TORCH: &[&str] = &["pt", "pth", "ckpt"];
+
+/// Path-taking APIs of the standard library and common data libraries.
+/// `json.load` and `yaml.safe_load` read streams or text, so their paths are
+/// found through the `open(...)` or `Path(...)` passed to them instead.
+const PATH_APIS: &[PathApi] = &[
+    // builtins and pathlib
+    api("open", &[0], &["file"]),
+    // io, gzip, bz2, lzma, codecs, tarfile, PIL.Image, ...
+    api("*.open", &[0], &["file", "filename", "fp", "name"]),
+    api("*.Path", &[0], &[]),
+    api("*.PurePath", &[0], &[]),
+    api("*.PosixPath", &[0], &[]),
+    api("*.WindowsPath", &[0], &[]),
+    api("*.PurePosixPath", &[0], &[]),
+    api("*.PureWindowsPath", &[0], &[]),
+    api("*.joinpath", &[0], &[]),
+    // os and os.path
+    api("os.path.join", &[0], &[]),
+    api("posixpath.join", &[0], &[]),
+    api("ntpath.join", &[0], &[]),
+    // Existence checks test whether a path is missing, so it may well be.
+    api("os.path.exists", &[0], &["path"]).creates(&[0], &["path"]),
+    api("os.path.isfile", &[0], &["path"])
+        .expects(FILE, &[])
+        .creates(&[0], &["path"]),
+    api("os.path.isdir", &[0], &["s"])
+        .expects(DIR, &[])
+        .creates(&[0], &["s"]),
+    api("os.path.getsize", &[0], &["filename"]).expects(FILE, &[]),
+    api("os.path.getmtime", &[0], &["filename"]),
+    api("os.listdir", &[0], &["path"]).expects(DIR, &[]),
+    api("os.scandir", &[0], &["path"]).expects(DIR, &[]),
+    api("os.walk", &[0], &["top"]).expects(DIR, &[]),
+    api("os.chdir", &[0], &["path"]).expects(DIR, &[]),
+    api("os.makedirs", &[0], &["name"])
+        .expects(DIR, &[])
+        .creates(&[0], &["name"]),
+    api("os.mkdir", &[0], &["path"])
+        .expects(DIR, &[])
+        .creates(&[0], &["path"]),
+    api("os.rmdir", &[0], &["path"]).expects(DIR, &[]),
+    api("os.remove", &[0], &["path"]).expects(FILE, &[]),
+    api("os.unlink", &[0], &["path"]).expects(FILE, &[]),
+    api("os.stat", &[0], &["path"]),
+    api("os.rename", &[0, 1], &["src", "dst"]).creates(&[1], &["dst"]),
+    api("os.replace", &[0, 1], &["src", "dst"]).creates(&[1], &["dst"]),
+    // shutil and glob
+    api("shutil.copy", &[0, 1], &["src", "dst"]).creates(&[1], &["dst"]),
+    api("shutil.copy2", &[0, 1], &["src", "dst"]).creates(&[1], &["dst"]),
+    api("shutil.copyfile", &[0, 1], &["src", "dst"])
+        .expects(FILE, &[])
+        .creates(&[1], &["dst"]),
+    api("shutil.copytree", &[0, 1], &["src", "dst"])
+        .expects(DIR, &[])
+        .creates(&[1], &["dst"]),
+    api("shutil.move", &[0, 1], &["src", "dst"]).creates(&[1], &["dst"]),
+    api("shutil.rmtree", &[0], &["path"]).expects(DIR, &[]),
+    api("glob.glob", &[0], &["pathname", "root_dir"]),
+    api("glob.iglob", &[0], &["pathname", "root_dir"]),
+    // subprocess working directories and executables
+    api("subprocess.run", &[], &[]).keyword_kinds(SUBPROCESS_KEYWORDS),
+    api("subprocess.call", &[], &[]).keyword_kinds(SUBPROCESS_KEYWORDS),
+    api("subprocess.check_call", &[], &[]).keyword_kinds(SUBPROCESS_KEYWORDS),
+    api("subprocess.check_output", &[], &[]).keyword_kinds(SUBPROCESS_KEYWORDS),
+    api("subprocess.Popen", &[], &[]).keyword_kinds(SUBPROCESS_KEYWORDS),
+    // logging
+    api("logging.FileHandler", &[0], &["filename"])
+        .expects(FILE, &["log"])
+        .creates(&[0], &["filename"]),
+    api("logging.handlers.RotatingFileHandler", &[0], &["filename"])
+        .expects(FILE, &["log"])
+        .creates(&[0], &["filename"]),
+    api(
+        "logging.handlers.TimedRotatingFileHandler",
+        &[0],
+        &["filename"],
+    )
+    .expects(FILE, &["log"])
+    .creates(&[0], &["filename"]),
+    api("logging.handlers.WatchedFileHandler", &[0], &["filename"])
+        .expects(FILE, &["log"])
+        .creates(&[0], &["filename"]),
+    api("logging.basicConfig", &[], &["filename"])
+        .expects(FILE, &["log"])
+        .creates(&[], &["filename"]),
+    // databases and archives
+    api("sqlite3.connect", &[0], &["database"])
+        .expects(FILE, &["db", "sqlite", "sqlite3"])
+        .creates(&[0], &["database"]),
+    api("zipfile.ZipFile", &[0], &["file"]).expects(FILE, &["zip"]),
+    api("tarfile.TarFile", &[0], &["name"]).expects(FILE, &["tar"]),
+    api("h5py.File", &[0], &["name"]).expects(FILE, HDF),
+    // config files
+    api("omegaconf.OmegaConf.load", &[0], &["file_"]).expects(FILE, YAML),
+    api("srsly.read_json", &[0], &["path"]).expects(FILE, &["json"]),
+    api("srsly.read_jsonl", &[0], &["path"]).expects(FILE, &["jsonl"]),
+    api("srsly.read_yaml", &[0], &["path"]).expects(FILE, YAML),
+    // numpy
+    api("numpy.load", &[0], &["file"]).expects(FILE, &["npy", "npz"]),
+    api("numpy.save", &[0], &["file"])
+        .expects(FILE, &["npy"])
+        .creates(&[0], &["file"]),
+    api("numpy.savez", &[0], &["file"])
+        .expects(FILE, &["npz"])
+        .creates(&[0], &["file"]),
+    api("numpy.savez_compressed", &[0], &["file"])
+        .expects(FILE, &["npz"])
+        .creates(&[0], &["file"]),
+    api("numpy.loadtxt", &[0], &["fname"]).expects(FILE, TEXT_TABLE),
+    api("numpy.savetxt", &[0], &["fname"])
+        .expects(FILE, TEXT_TABLE)
+        .creates(&[0], &["fname"]),
+    api("numpy.genfromtxt", &[0], &["fname"]).expects(FILE, TEXT_TABLE),
+    api("numpy.fromfile", &[0], &["file"]),
+    api("numpy.memmap", &[0], &["filename"]),
+    // torch
+    api("torch.load", &[0], &["f"]).expects(FILE, TORCH),
+    api("torch.save", &[1], &["f"])
+        .expects(FILE, TORCH)
+        .creates(&[1], &["f"]),
+    api("torch.jit.load", &[0], &["f"]).expects(FILE, TORCH),
+    api("torch.jit.save", &[1], &["f"])
+        .expects(FILE, TORCH)
+        .creates(&[1], &["f"]),
+    // images
+    api("cv2.imread", &[0], &["filename"]).expects(FILE, IMAGES),
+    api("cv2.imwrite", &[0], &["filename"])
+        .expects(FILE, IMAGES)
+        .creates(&[0], &["filename"]),
+    api("cv2.VideoCapture", &[0], &["filename"]),
+    api("matplotlib.pyplot.imread", &[0], &["fname"]).expects(FILE, IMAGES),
+    api("matplotlib.pyplot.savefig", &[0], &["fname"])
+        .expects(FILE, FIGURES)
+        .creates(&[0], &["fname"]),
+    // pandas readers, as functions and re-exports
+    api("*.read_csv", &[0], PANDAS_READ_KEYWORDS).expects(FILE, CSV),
+    api("*.read_table", &[0], PANDAS_READ_KEYWORDS).expects(FILE, TEXT_TABLE),
+    api("*.read_fwf", &[0], PANDAS_READ_KEYWORDS).expects(FILE, TEXT_TABLE),
+    api("*.read_json", &[0], PANDAS_READ_KEYWORDS).expects(FILE, JSON),
+    api("*.read_excel", &[0], PANDAS_READ_KEYWORDS).expects(FILE, EXCEL),
+    api("*.read_parquet", &[0], PANDAS_READ_KEYWORDS)
+        .expects(ExpectedKind::Any, &["parquet", "pq"]),
+    api("*.read_feather", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["feather"]),
+    api("*.read_orc", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["orc"]),
+    api("*.read_pickle", &[0], PANDAS_READ_KEYWORDS).expects(FILE, PICKLE),
+    api("*.read_hdf", &[0], PANDAS_READ_KEYWORDS).expects(FILE, HDF),
+    api("*.read_stata", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["dta"]),
+    api("*.read_sas", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["sas7bdat", "xpt"]),
+    api("*.read_spss", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["sav"]),
+    api("*.read_xml", &[0], PANDAS_READ_KEYWORDS).expects(FILE, &["xml"]),
+    // pandas writers, as DataFrame and Series methods
+    api("*.to_csv", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, CSV)
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_json", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, JSON)
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_excel", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, EXCEL)
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_parquet", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(ExpectedKind::Any, &["parquet", "pq"])
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_feather", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, &["feather"])
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_orc", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, &["orc"])
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_pickle", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, PICKLE)
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_hdf", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, HDF)
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_stata", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, &["dta"])
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+    api("*.to_xml", &[0], PANDAS_WRITE_KEYWORDS)
+        .expects(FILE, &["xml"])
+        .creates(&[0], PANDAS_WRITE_KEYWORDS),
+];
+
+/// `pathlib` methods that operate on a directory.
+const DIRECTORY_METHODS: &[&str] = &["iterdir", "glob", "rglob", "walk"];
+
+/// `pathlib` methods that check whether a path exists.
+const EXISTENCE_METHODS: &[&str] = &["exists", "is_file", "is_dir"];
+
+/// Returns the method called on the result of the call whose argument starts
+/// at the beginning of `after`, as `iterdir` in `Path("data/").iterdir()`.
+/// Only calls closed on the same line are followed.
+fn chained_method(after: &str) -> Option<&str> {
+    let line = &after[..after.find('\n').unwrap_or(after.len())];
+    let strings = tokenize_strings(line);
+    let mut strings = strings.iter().peekable();
+    let bytes = line.as_bytes();
+    let mut depth = 1usize;
+    let mut i = 0usize;
+    while i < bytes.len() {
+        if let Some(string) = strings.next_if(|string| string.start == i) {
+            i = string.end();
+            continue;
+        }
+        match bytes[i] {
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    let rest = line[i + 1..].trim_start().strip_prefix('.')?;
+                    let rest = rest.trim_start();
+                    let end = rest
+                        .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+                        .unwrap_or(rest.len());
+                    return Some(&rest[..end]);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Conventional import aliases, expanded before matching `PATH_APIS`.
+const MODULE_ALIASES: &[(&str, &str)] = &[
+    ("np", "numpy"),
+    ("pd", "pandas"),
+    ("plt", "matplotlib.pyplot"),
+];
+
+/// Finds the catalogued API whose path argument the call context is at.
+fn catalogued_api(ctx: &CallContext) -> Option<&'static PathApi> {
+    let full = expand_alias(&ctx.full_name);
+    PATH_APIS
+        .iter()
+        .find(|api| calls_api(api.call, &full) && api.takes_argument(ctx))
+}
+
+fn expand_alias(full: &str) -> String {
+    for (alias, module) in MODULE_ALIASES {
+        if let Some(rest) = full.strip_prefix(alias).and_then(|r| r.strip_prefix('.')) {
+            return format!("{module}.{rest}");
+        }
+    }
+    full.to_string()
+}
+
+/// Whether a call written as `full` calls `pattern`. Dotted calls match a
+/// trailing part of the pattern, as `Image.open` after `from PIL import
+/// Image`; bare names only match patterns without a module.
+fn calls_api(pattern: &str, full: &str) -> bool {
+    if let Some(name) = pattern.strip_prefix("*.") {
+        return full == name || ends_with_part(full, name);
+    }
+    pattern == full || (full.contains('.') && ends_with_part(pattern, full))
+}
+
+/// Whether `dotted` ends with `part` at a `.` boundary.
+fn ends_with_part(dotted: &str, part: &str) -> bool {
+    dotted
+        .strip_suffix(part)
+        .is_some_and(|module| module.ends_with('.'))
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '.'
 }
 
 fn matches_named_path_arg(name: &str) -> bool {
     matches!(name, "path" | "filepath" | "filename" | "file" | "fname")
 }
 
-fn path_join_operator_context(text: &str, string_start_offset: usize) -> bool {
-    let window_start = string_start_offset.saturating_sub(120);
-    let window = &text[window_start..string_start_offset];
-    let path_pos = window.rfind("Path(");
-    let path_mod_pos = window.rfind("pathlib.Path(");
-    let path_start = path_pos.or(path_mod_pos);
-    let Some(path_start) = path_start else {
-        return false;
-    };
-    let after = &window[path_start..];
-    after.contains("/")
+/// Whether the string at `string_start_offset` is joined onto a `pathlib`
+/// path with `/`, as in `Path(root) / "` or `Path.home() / name / "`. The
+/// leftmost operand of the `/` chain decides.
+fn path_join_operator_context(
+    text: &str,
+    tokens: &[StringToken],
+    string_start_offset: usize,
+) -> bool {
+    join_operands(text, tokens, string_start_offset)
+        .iter()
+        .any(|&start| is_path_operand(&text[start..]))
+}
+
+/// Where the expression that joins the string at `string_start_offset` onto
+/// a base starts: the leftmost operand of a `/` chain, or the name of an
+/// `os.path.join(...)` or `.joinpath(...)` call. Nested joins resolve to the
+/// outermost one, whose enclosing call says what the joined path is for.
+pub fn join_expression_start(
+    text: &str,
+    tokens: &[StringToken],
+    string_start_offset: usize,
+) -> Option<usize> {
+    let mut start = None;
+    let mut offset = string_start_offset;
+    loop {
+        if let Some(&leftmost) = join_operands(text, tokens, offset).last() {
+            offset = leftmost;
+        } else {
+            match detect_call_context(text, tokens, offset) {
+                Some(ctx)
+                    if is_join_function(&ctx.full_name) || ctx.full_name.ends_with(".joinpath") =>
+                {
+                    offset = ctx.name_start;
+                }
+                _ => return start,
+            }
+        }
+        start = Some(offset);
+    }
+}
+
+/// Start offsets of the operands joined with `/` before `offset`, nearest
+/// first. Operands are names, attributes, calls, subscripts and strings.
+fn join_operands(text: &str, tokens: &[StringToken], offset: usize) -> Vec<usize> {
+    let mut operands = Vec::new();
+    let mut code = code_before(text, tokens, offset).peekable();
+    loop {
+        while code
+            .next_if(|(_, byte)| byte.is_ascii_whitespace() || *byte == b'\\')
+            .is_some()
+        {}
+        if code.next().is_none_or(|(_, byte)| byte != b'/') {
+            return operands;
+        }
+        // `//` divides.
+        if code.next_if(|(_, byte)| *byte == b'/').is_some() {
+            return operands;
+        }
+        while code
+            .next_if(|(_, byte)| byte.is_ascii_whitespace() || *byte == b'\\')
+            .is_some()
+        {}
+        if let Some((idx, _)) = code.next_if(|(_, byte)| *byte == b'"') {
+            operands.push(idx);
+            continue;
+        }
+        // Walk back over the operand: names, attributes, calls and subscripts.
+        let mut depth = 0usize;
+        let mut start = None;
+        while let Some(&(idx, byte)) = code.peek() {
+            match byte {
+                b')' | b']' => depth += 1,
+                b'(' | b'[' if depth > 0 => depth -= 1,
+                _ if depth > 0 => {}
+                b'.' => {}
+                _ if byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80 => {}
+                _ => break,
+            }
+            start = Some(idx);
+            code.next();
+        }
+        match start {
+            Some(start) => operands.push(start),
+            None => return operands,
+        }
+    }
+}
+
+/// Whether the operand at the start of `text` builds a `pathlib` path, as
+/// `Path(...)`, `pathlib.Path(...).parent` or `Path.home()` do.
+fn is_path_operand(text: &str) -> bool {
+    let head_len = text
+        .find(|ch: char| !is_name_char(ch) && ch.is_ascii())
+        .unwrap_or(text.len());
+    let head = &text[..head_len];
+    let class = head
+        .strip_suffix(".home")
+        .or_else(|| head.strip_suffix(".cwd"))
+        .unwrap_or(head);
+    text[head_len..].starts_with('(') && is_path_class(class)
 }
 
 #[cfg(test)]
@@ -141,34 +937,397 @@ mod tests {
     fn detects_open_context() {
         let text = "with open(\"./foo\") as f:";
         let offset = text.find('\"').unwrap();
-        assert!(is_path_context(text, offset));
+        assert!(is_path_context(
+            text,
+            &tokenize_strings(text),
+            offset,
+            &Config::default(),
+            &[]
+        ));
     }
 
     #[test]
     fn detects_pathlib_context() {
         let text = "Path(\"./foo\")";
         let offset = text.find('\"').unwrap();
-        assert!(is_path_context(text, offset));
+        assert!(is_path_context(
+            text,
+            &tokenize_strings(text),
+            offset,
+            &Config::default(),
+            &[]
+        ));
     }
 
     #[test]
     fn detects_pandas_context() {
         let text = "pandas.read_csv(\"data.csv\")";
         let offset = text.find('\"').unwrap();
-        assert!(is_path_context(text, offset));
+        assert!(is_path_context(
+            text,
+            &tokenize_strings(text),
+            offset,
+            &Config::default(),
+            &[]
+        ));
     }
 
     #[test]
     fn ignores_non_path_context() {
         let text = "print(\"hello\")";
         let offset = text.find('\"').unwrap();
-        assert!(!is_path_context(text, offset));
+        assert!(!is_path_context(
+            text,
+            &tokenize_strings(text),
+            offset,
+            &Config::default(),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn counts_arguments_outside_strings_comments_and_brackets() {
+        let call = |text: &str| {
+            let ctx =
+                detect_call_context(text, &tokenize_strings(text), text.rfind("\"a").unwrap())?;
+            Some((ctx.full_name, ctx.arg_index, ctx.named_arg))
+        };
+        assert_eq!(call("f(\"a\")"), Some(("f".into(), 0, None)));
+        assert_eq!(
+            call("f(\"x,y)\", (1, 2), \"a\")"),
+            Some(("f".into(), 2, None))
+        );
+        assert_eq!(
+            call("f(x, mode=\"r\", path = \"a\")"),
+            Some(("f".into(), 2, Some("path".into())))
+        );
+        assert_eq!(call("f(a == \"a\")"), Some(("f".into(), 0, None)));
+        assert_eq!(
+            call("open(f\"({x}\", \"a\")"),
+            Some(("open".into(), 1, None))
+        );
+        assert_eq!(
+            call("load(\n    x,  # (a, b\n    src=  # path\n    \"a\")"),
+            Some(("load".into(), 1, Some("src".into())))
+        );
+        let long = format!("load(\n{}    \"a\")", "    x,\n".repeat(60));
+        assert_eq!(call(&long), Some(("load".into(), 60, None)));
+        assert_eq!(call("f(\ndef g():\n    return \"a\""), None);
+        assert_eq!(call("load(\nx,\n\"a/\")"), Some(("load".into(), 1, None)));
+        assert_eq!(call("f(\nreturn \"a\""), None);
+        let far = format!("load(\n{}    \"a\")", "    x,\n".repeat(MAX_BRACKET_LINES));
+        assert_eq!(call(&far), None);
+    }
+
+    #[test]
+    fn joins_onto_pathlib_paths_only() {
+        let joined = |text: &str| {
+            path_join_operator_context(text, &tokenize_strings(text), text.rfind("\"a").unwrap())
+        };
+        for text in [
+            "Path(root) / \"a\"",
+            "pathlib.Path(__file__).parent / \"a\"",
+            "Path.home() / \".config\" / \"a\"",
+            "Path(root) / name / \"a\"",
+            "x = (Path(root)  # base\n     / \"a\")",
+        ] {
+            assert!(joined(text), "{text}");
+        }
+        for text in [
+            "Path(\"x/y\", \"a\")",
+            "total / \"a\"",
+            "print(Path(x), 1 / 2, \"a\")",
+            "Path(x) // \"a\"",
+            "\"Path(x) / \" + \"a\"",
+        ] {
+            assert!(!joined(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn path_builders_start_a_path() {
+        for text in [
+            "os.path.join(\"data\", \"raw\")",
+            "ntpath.join(\"data\")",
+            "root.joinpath(\"data\")",
+        ] {
+            assert!(
+                is_path_context(
+                    text,
+                    &tokenize_strings(text),
+                    text.find('\"').unwrap(),
+                    &Config::default(),
+                    &[]
+                ),
+                "{text}"
+            );
+        }
+        let text = "os.path.join(root, \"raw\")";
+        assert!(!is_path_context(
+            text,
+            &tokenize_strings(text),
+            text.find('\"').unwrap(),
+            &Config::default(),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn binds_paths_by_name_annotation_and_container() {
+        let config = Config::default();
+        let expectation = |text: &str| {
+            path_expectation(
+                text,
+                &tokenize_strings(text),
+                text.rfind("\"d").unwrap(),
+                &config,
+                &[],
+            )
+        };
+        let kind = |text: &str| expectation(text).map(|expectation| expectation.kind);
+        assert_eq!(kind("TRAIN_PATH = \"data/\""), Some(ExpectedKind::Any));
+        assert_eq!(kind("self.cache_dir = \"data/\""), Some(ExpectedKind::Dir));
+        assert_eq!(
+            kind("run(config, log_file=\"data/\")"),
+            Some(ExpectedKind::File)
+        );
+        assert_eq!(
+            kind("    data_root: str = \"data/\""),
+            Some(ExpectedKind::Dir)
+        );
+        assert_eq!(
+            kind("    output: Path | None = \"data/\""),
+            Some(ExpectedKind::Any)
+        );
+        assert_eq!(
+            kind("    src_dir: str = field(default=\"data/\")"),
+            Some(ExpectedKind::Dir)
+        );
+        assert_eq!(
+            kind("    src_dir: str = Field(\"data/\", alias=\"src\")"),
+            Some(ExpectedKind::Dir)
+        );
+        assert_eq!(
+            kind("INPUT_FILES = [\"a.csv\", \"data/\"]"),
+            Some(ExpectedKind::File)
+        );
+        assert_eq!(
+            kind("search_roots = (\"a\", \"data/\")"),
+            Some(ExpectedKind::Dir)
+        );
+        assert_eq!(
+            kind("CONFIG = {\"log_dir\": \"data/\"}"),
+            Some(ExpectedKind::Dir)
+        );
+        assert_eq!(
+            kind("splits = {\"train\": \"data/train.csv\"}"),
+            Some(ExpectedKind::Any)
+        );
+        assert_eq!(
+            kind("splits = (\"data/\", \"b/\")"),
+            Some(ExpectedKind::Any)
+        );
+        assert!(
+            expectation("TRAIN_PATH = \"data/\"")
+                .unwrap()
+                .may_be_missing
+        );
+
+        assert_eq!(kind("name = \"data\""), None);
+        assert_eq!(kind("if x_path == \"data\":"), None);
+        assert_eq!(kind("splits = {\"train\": \"data\"}"), None);
+        assert!(!looks_like_path("1/2.5") && !looks_like_path("3/4.0"));
+        assert!(looks_like_path("runs/v1.0") && looks_like_path("2024/q1/"));
+        assert_eq!(kind("row[\"data/x.csv\"]"), None);
+        assert_eq!(
+            kind("dates = [\"2020/01/02\", \"data/\"]"),
+            Some(ExpectedKind::Any)
+        );
+        assert_eq!(kind("dates = [\"data/01/02\"]"), None);
     }
 
     #[test]
     fn allows_named_path_arg() {
         let text = "load_data(path=\"./data.csv\")";
         let offset = text.find('\"').unwrap();
-        assert!(is_path_context(text, offset));
+        assert!(is_path_context(
+            text,
+            &tokenize_strings(text),
+            offset,
+            &Config::default(),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn consults_path_function_rules() {
+        let config = Config {
+            path_functions: vec![PathFunctionRule {
+                call: "mylib.io.load".into(),
+                args: vec![ArgSlot::Position(1), ArgSlot::Keyword("src".into())],
+                kind: ExpectedKind::File,
+                extensions: vec!["npz".into()],
+            }],
+            ..Config::default()
+        };
+        let expectation = |text: &str| {
+            path_expectation(
+                text,
+                &tokenize_strings(text),
+                text.find('\"').unwrap(),
+                &config,
+                &[],
+            )
+        };
+        let expected = Some(PathExpectation {
+            kind: ExpectedKind::File,
+            extensions: vec!["npz".into()],
+            may_be_missing: false,
+        });
+        assert_eq!(expectation("mylib.io.load(x, \"a.npz\")"), expected);
+        assert_eq!(expectation("io.load(src=\"a.npz\")"), expected);
+        assert_eq!(expectation("load(x, \"a.npz\")"), expected);
+        assert_eq!(expectation("mylib.io.load(\"a.npz\")"), None);
+        assert_eq!(expectation("yourlib.load(x, \"a.npz\")"), None);
+        assert_eq!(expectation("myio.load(x, \"a.npz\")"), None);
+    }
+
+    #[test]
+    fn consults_workspace_signatures_exactly() {
+        let signatures = [PathFunctionRule {
+            call: "pio.load".into(),
+            args: vec![ArgSlot::Position(0), ArgSlot::Keyword("src".into())],
+            kind: ExpectedKind::Any,
+            extensions: Vec::new(),
+        }];
+        let context = |text: &str| {
+            is_path_context(
+                text,
+                &tokenize_strings(text),
+                text.find('\"').unwrap(),
+                &Config::default(),
+                &signatures,
+            )
+        };
+        assert!(context("pio.load(\"a\")"));
+        assert!(context("pio.load(src=\"a\")"));
+        assert!(!context("pio.load(x, \"a\")"));
+        assert!(!context("other.pio.load(\"a\")"));
+        assert!(!context("load(\"a\")"));
+    }
+
+    #[test]
+    fn expectations_filter_and_rank_entries() {
+        let dirs = PathExpectation {
+            kind: ExpectedKind::Dir,
+            extensions: Vec::new(),
+            may_be_missing: false,
+        };
+        assert!(dirs.accepts(true));
+        assert!(!dirs.accepts(false));
+        let arrays = PathExpectation {
+            kind: ExpectedKind::File,
+            extensions: vec!["npz".into(), "csv.gz".into()],
+            may_be_missing: false,
+        };
+        assert!(arrays.accepts(false));
+        assert!(arrays.prefers("Weights.NPZ"));
+        assert!(arrays.prefers("table.csv.gz"));
+        assert!(!arrays.prefers("npz"));
+        assert!(!arrays.prefers("table.gz"));
+    }
+
+    #[test]
+    fn catalogue_has_one_row_per_call() {
+        for (idx, api) in PATH_APIS.iter().enumerate() {
+            assert!(
+                PATH_APIS[idx + 1..]
+                    .iter()
+                    .all(|other| other.call != api.call),
+                "{}",
+                api.call
+            );
+        }
+    }
+
+    #[test]
+    fn catalogue_covers_path_arguments() {
+        let config = Config::default();
+        let context = |text: &str| {
+            is_path_context(
+                text,
+                &tokenize_strings(text),
+                text.rfind("\"a\"").unwrap(),
+                &config,
+                &[],
+            )
+        };
+        for text in [
+            "shutil.copy(src, \"a\")",
+            "shutil.move(\"x\", dst=\"a\")",
+            "os.rename(x, \"a\")",
+            "np.load(\"a\")",
+            "torch.save(model, \"a\")",
+            "Image.open(\"a\")",
+            "tarfile.open(name=\"a\")",
+            "subprocess.run(cmd, check=True, cwd=\"a\")",
+            "subprocess.Popen(cmd, executable=\"a\")",
+            "handlers.RotatingFileHandler(\"a\")",
+            "df.to_parquet(\"a\")",
+            "pandas.read_csv(sep=\",\", filepath_or_buffer=\"a\")",
+            "json.load(open(\"a\"))",
+            "yaml.safe_load(Path(\"a\").read_text())",
+        ] {
+            assert!(context(text), "{text}");
+        }
+        for text in [
+            "torch.save(\"a\", f)",
+            "subprocess.run(\"a\")",
+            "open(p, \"a\")",
+            "load(\"a\")",
+            "mylib.save(x, \"a\")",
+        ] {
+            assert!(!context(text), "{text}");
+        }
+    }
+
+    #[test]
+    fn infers_expectations_from_the_call() {
+        let config = Config::default();
+        let expectation = |text: &str| {
+            path_expectation(
+                text,
+                &tokenize_strings(text),
+                text.find("\"d").unwrap(),
+                &config,
+                &[],
+            )
+            .unwrap()
+        };
+        let dirs = PathExpectation {
+            kind: ExpectedKind::Dir,
+            extensions: Vec::new(),
+            may_be_missing: false,
+        };
+        assert_eq!(expectation("os.chdir(\"data/\")"), dirs);
+        assert_eq!(expectation("shutil.rmtree(path=\"data/\")"), dirs);
+        assert_eq!(expectation("for p in Path(\"data/\").iterdir():"), dirs);
+        assert_eq!(expectation("Path(\"data/)\").glob(\"*.csv\")"), dirs);
+        assert_eq!(expectation("subprocess.run(cmd, cwd=\"data/\")"), dirs);
+        assert_eq!(
+            expectation("subprocess.run(cmd, executable=\"data/\")").kind,
+            ExpectedKind::File
+        );
+        assert_eq!(
+            expectation("Path(\"data/\").read_text()").kind,
+            ExpectedKind::Any
+        );
+        let csv = expectation("pd.read_csv(\"data/\")");
+        assert_eq!(csv.kind, ExpectedKind::File);
+        assert!(csv.prefers("train.csv.gz") && csv.prefers("a.tsv") && !csv.prefers("a.png"));
+        let parquet = expectation("pd.read_parquet(\"data/\")");
+        assert_eq!(parquet.kind, ExpectedKind::Any);
+        assert!(parquet.prefers("part-0.parquet"));
+        assert!(expectation("torch.load(\"data/\")").prefers("model.ckpt"));
     }
 }
@@ -0,0 +1,237 @@
+use std::path::Path;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Range};
+
+use crate::completion::{find_string_literals, resolve_literal_paths, StringLiteral};
+use crate::config::{Config, DiagnosticsSeverity, PathFunctionRule};
+use crate::context::{join_expression_start, path_expectation};
+use crate::eval::{join_path_text, literal_base, Bindings};
+use crate::tokenizer::{LiteralKind, StringToken};
+
+pub const MISSING_PATH_CODE: &str = "missing-path";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingPath {
+    pub range: Range,
+    pub content: String,
+    /// `content` joined onto the path the literal is joined onto, if any;
+    /// always ends with `content`.
+    pub path: String,
+    /// How the literal is written, for escaping replacement text.
+    pub kind: LiteralKind,
+}
+
+/// Finds every literal in a path context whose target does not exist.
+pub fn find_missing_paths(
+    text: &str,
+    tokens: &[StringToken],
+    bindings: &Bindings,
+    signatures: &[PathFunctionRule],
+    file_dir: Option<&Path>,
+    root_dir: Option<&Path>,
+    config: &Config,
+) -> Vec<MissingPath> {
+    let mut missing = Vec::new();
+    for literal in find_string_literals(text, tokens) {
+        if !is_checkable(&literal) {
+            continue;
+        }
+        let base = literal_base(text, tokens, literal.string_start_byte, bindings);
+        // Arguments the call creates, like `df.to_csv(...)`, and values that
+        // are paths only by name need not exist. A joined literal such as
+        // `df.to_csv(DATA / "out.csv")` answers to the call around the join.
+        let expectation = match base {
+            Some(_) => join_expression_start(text, tokens, literal.string_start_byte)
+                .and_then(|start| path_expectation(text, tokens, start, config, signatures)),
+            None => path_expectation(text, tokens, literal.string_start_byte, config, signatures),
+        };
+        // Unjoined literals outside any path context are not paths at all.
+        let skip = match expectation {
+            Some(expectation) => expectation.may_be_missing,
+            None => base.is_none(),
+        };
+        if skip {
+            continue;
+        }
+        if is_creation_target(&text[literal.content_end_byte..]) {
+            continue;
+        }
+        let path = match base {
+            Some(base) => join_path_text(&base, &literal.content),
+            None => literal.content.clone(),
+        };
+        let candidates = resolve_literal_paths(&path, file_dir, root_dir, config);
+        if candidates.is_empty() || candidates.iter().any(|path| path.exists()) {
+            continue;
+        }
+        missing.push(MissingPath {
+            range: literal.content_range,
+            content: literal.content,
+            path,
+            kind: literal.kind,
+        });
+    }
+    missing
+}
+
+pub fn missing_path_diagnostic(missing: &MissingPath, config: &Config) -> Option<Diagnostic> {
+    Some(Diagnostic {
+        range: missing.range,
+        severity: Some(lsp_severity(config.diagnostics_severity)?),
+        code: Some(NumberOrString::String(MISSING_PATH_CODE.into())),
+        source: Some("pathy".into()),
+        message: format!("Path not found: {}", missing.content),
+        ..Diagnostic::default()
+    })
+}
+
+fn lsp_severity(severity: DiagnosticsSeverity) -> Option<DiagnosticSeverity> {
+    match severity {
+        DiagnosticsSeverity::Off => None,
+        DiagnosticsSeverity::Error => Some(DiagnosticSeverity::ERROR),
+        DiagnosticsSeverity::Warning => Some(DiagnosticSeverity::WARNING),
+        DiagnosticsSeverity::Information => Some(DiagnosticSeverity::INFORMATION),
+        DiagnosticsSeverity::Hint => Some(DiagnosticSeverity::HINT),
+    }
+}
+
+fn is_checkable(literal: &StringLiteral) -> bool {
+    let content = literal.content.as_str();
+    if content.trim().is_empty() || content.contains("://") || content.contains('*') {
+        return false;
+    }
+    !(literal.is_fstring && content.contains('{'))
+}
+
+/// Paths that are about to be written or created are expected to be missing:
+/// `open(p, "w")`, `Path(p).mkdir()` and friends.
+fn is_creation_target(after_literal: &str) -> bool {
+    let rest = after_literal.trim_start_matches(['"', '\'']).trim_start();
+    if let Some(args) = rest.strip_prefix(',') {
+        let args = args.trim_start();
+        let args = args.strip_prefix("mode").map_or(args, |a| {
+            a.trim_start().trim_start_matches('=').trim_start()
+        });
+        let Some(quote) = args.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            return false;
+        };
+        let mode = args[1..].split(quote).next().unwrap_or("");
+        return mode.contains(['w', 'a', 'x']);
+    }
+    let Some(after_call) = rest.strip_prefix(')') else {
+        return false;
+    };
+    [".mkdir(", ".touch(", ".write_text(", ".write_bytes("]
+        .iter()
+        .any(|method| after_call.starts_with(method))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use crate::tokenizer::tokenize_strings;
+    use lsp_types::Position;
+
+    fn diagnose(text: &str, file_dir: Option<&Path>, config: &Config) -> Vec<Diagnostic> {
+        let tokens = tokenize_strings(text);
+        let bindings = Bindings::scan(text, &tokens, None);
+        find_missing_paths(text, &tokens, &bindings, &[], file_dir, None, config)
+            .iter()
+            .filter_map(|missing| missing_path_diagnostic(missing, config))
+            .collect()
+    }
+
+    #[test]
+    fn reports_missing_path_in_open() {
+        let text = "import os\nwith open(\"./definitely-missing/data.csv\") as f:\n";
+        let config = Config::default();
+        let diagnostics = diagnose(text, Some(Path::new("/nonexistent-pathy")), &config);
+        assert_eq!(diagnostics.len(), 1);
+        let range = diagnostics[0].range;
+        assert_eq!(range.start, Position::new(1, 11));
+        assert_eq!(range.end, Position::new(1, 40));
+    }
+
+    #[test]
+    fn skips_existing_and_non_path_strings() {
+        let dir = std::env::temp_dir();
+        let text = "open(\"./\")\nprint(\"./missing\")\n";
+        let config = Config::default();
+        let diagnostics = diagnose(text, Some(&dir), &config);
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn resolves_literals_against_their_joined_base() {
+        let root = TempDir::new("diag-base");
+        std::fs::create_dir_all(root.join("data").join("raw")).unwrap();
+        let text = "DATA = Path(\"data\")\nx = DATA / \"raw\"\ny = DATA / \"raw/missing\"\n";
+        let config = Config::default();
+        let diagnostics = diagnose(text, Some(&root), &config);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(2, 12));
+    }
+
+    #[test]
+    fn skips_paths_the_call_creates() {
+        let text = "df.to_csv(\"out/missing.csv\")\nshutil.copy(\"in/missing\", \"out/missing\")\n";
+        let diagnostics = diagnose(
+            text,
+            Some(Path::new("/nonexistent-pathy")),
+            &Config::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(1, 13));
+    }
+
+    #[test]
+    fn skips_joined_paths_the_call_creates() {
+        let root = TempDir::new("diag-joined");
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        let text = "DATA = Path(\"data\")\n\
+            df.to_csv(DATA / \"out.csv\")\n\
+            os.makedirs(os.path.join(\"data\", \"new\"))\n\
+            df.to_csv(path_or_buf=DATA / \"sub\" / \"out.csv\")\n\
+            open(DATA / \"missing.csv\")\n";
+        let diagnostics = diagnose(text, Some(&root), &Config::default());
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(4, 13));
+    }
+
+    #[test]
+    fn skips_existence_checks() {
+        let text = "if not os.path.exists(\"cache/\"):\n    os.makedirs(\"cache/\")\n\
+            if os.path.isfile(\"out.csv\"):\n    pass\n\
+            if not Path(\"runs\").is_dir():\n    pass\n\
+            open(\"missing.txt\")\n";
+        let diagnostics = diagnose(
+            text,
+            Some(Path::new("/nonexistent-pathy")),
+            &Config::default(),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(6, 6));
+    }
+
+    #[test]
+    fn skips_write_targets() {
+        assert!(is_creation_target("\", \"w\") as f:"));
+        assert!(is_creation_target("\", mode='a')"));
+        assert!(is_creation_target("\").mkdir(parents=True)"));
+        assert!(!is_creation_target("\", \"rb\")"));
+        assert!(!is_creation_target("\")"));
+    }
+
+    #[test]
+    fn severity_off_disables_diagnostics() {
+        let config = Config {
+            diagnostics_severity: DiagnosticsSeverity::Off,
+            ..Config::default()
+        };
+        let text = "open(\"./missing\")";
+        assert!(diagnose(text, None, &config).is_empty());
+    }
+}
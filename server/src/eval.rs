@@ -0,0 +1,832 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::completion::is_windows_drive_prefix;
+use crate::tokenizer::{tokenize_strings, StringToken};
+
+/// A statically known value of a Python expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Str(String),
+    /// A `pathlib` path, kept as normalized text with `/` separators.
+    Path(String),
+}
+
+impl Value {
+    pub fn text(&self) -> &str {
+        match self {
+            Value::Str(text) | Value::Path(text) => text,
+        }
+    }
+}
+
+/// An intermediate result while evaluating: a value, or something that only
+/// becomes one when called, such as `os.path.join` or `p.joinpath`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Term {
+    Value(Value),
+    Name(String),
+    Method(Value, String),
+    /// `p.parents`, waiting to be indexed.
+    Parents(String),
+}
+
+/// Module-level names whose values pathy could evaluate, such as
+/// `DATA = Path("data")` or `ROOT = Path(__file__).parent`.
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+    file: Option<PathBuf>,
+    values: HashMap<String, Value>,
+}
+
+impl Bindings {
+    /// Evaluates the top-level assignments of a document in order. `file` is
+    /// the document's own path, the value of `__file__`.
+    pub fn scan(text: &str, tokens: &[StringToken], file: Option<&Path>) -> Self {
+        let mut bindings = Self {
+            file: file.map(Path::to_path_buf),
+            values: HashMap::new(),
+        };
+        let mut start = 0usize;
+        while start < text.len() {
+            let end = logical_line_end(text, tokens, start);
+            bindings.assign(&text[start..end]);
+            start = end + 1;
+        }
+        bindings
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    /// Records `NAME = expr` or `NAME: annotation = expr`. Any other statement
+    /// that rebinds a name forgets its value.
+    fn assign(&mut self, statement: &str) {
+        let name_len = statement.bytes().take_while(|b| is_name_byte(*b)).count();
+        if name_len == 0 || statement.as_bytes()[0].is_ascii_digit() {
+            return;
+        }
+        let name = &statement[..name_len];
+        let rest = statement[name_len..].trim_start();
+        let value_text = if let Some(annotated) = rest.strip_prefix(':') {
+            annotated
+                .find('=')
+                .map(|eq| &annotated[eq + 1..])
+                .filter(|value| !value.starts_with('='))
+        } else if let Some(value) = rest.strip_prefix('=') {
+            (!value.starts_with('=')).then_some(value)
+        } else {
+            let augmented = rest
+                .find('=')
+                .is_some_and(|eq| rest[..eq].bytes().all(|b| b"+-*/%@&|^<>".contains(&b)));
+            if augmented {
+                self.values.remove(name);
+            }
+            return;
+        };
+        let Some(value_text) = value_text else {
+            return;
+        };
+        match evaluate(value_text, self) {
+            Some(value) => {
+                self.values.insert(name.to_string(), value);
+            }
+            None => {
+                self.values.remove(name);
+            }
+        }
+    }
+}
+
+/// Evaluates a whole expression, or `None` when any part of it is unknown.
+pub fn evaluate(expr: &str, bindings: &Bindings) -> Option<Value> {
+    let mut parser = Parser::new(expr, bindings);
+    let term = parser.expression()?;
+    parser.skip_trivia();
+    if parser.pos < expr.len() {
+        return None;
+    }
+    match term {
+        Term::Value(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// The path a string literal is joined onto, when it is the right operand of
+/// `/`, a later argument of `os.path.join(...)`, `Path(...)` or
+/// `p.joinpath(...)` and everything before it evaluates. `string_start` is the
+/// literal's document offset, including any prefix.
+pub fn literal_base(
+    text: &str,
+    tokens: &[StringToken],
+    string_start: usize,
+    bindings: &Bindings,
+) -> Option<String> {
+    let scanner = Scanner { text, tokens };
+    let before = scanner.skip_space_back(string_start);
+    match text[..before].bytes().last()? {
+        b'/' if !text[..before - 1].ends_with('/') => {
+            let left_end = before - 1;
+            let left_start = scanner.operand_start(left_end)?;
+            match evaluate(&text[left_start..left_end], bindings)? {
+                Value::Path(base) => Some(base),
+                Value::Str(_) => None,
+            }
+        }
+        b',' => {
+            let (open, commas) = scanner.enclosing_call(before - 1)?;
+            let callee_start = scanner.primary_start(open)?;
+            let mut parser = Parser::new(&text[callee_start..open], bindings);
+            let callee = parser.term()?;
+            let mut args = Vec::new();
+            let mut arg_start = open + 1;
+            for comma in commas.into_iter().chain(std::iter::once(before - 1)) {
+                args.push(evaluate(&text[arg_start..comma], bindings)?);
+                arg_start = comma + 1;
+            }
+            let joined = match callee {
+                Term::Name(name) if is_join_function(&name) || is_path_class(&name) => {
+                    join_all(&args)
+                }
+                Term::Method(Value::Path(base), method) if method == "joinpath" => args
+                    .iter()
+                    .fold(base, |acc, arg| join_path_text(&acc, arg.text())),
+                _ => return None,
+            };
+            Some(joined)
+        }
+        _ => None,
+    }
+}
+
+/// Joins path text the way `os.path.join` does: an absolute right side
+/// replaces the left one.
+pub fn join_path_text(base: &str, rel: &str) -> String {
+    if base.is_empty() || is_absolute(rel) {
+        return rel.to_string();
+    }
+    if rel.is_empty() || base.ends_with(['/', '\\']) {
+        return format!("{base}{rel}");
+    }
+    format!("{base}/{rel}")
+}
+
+fn join_all(args: &[Value]) -> String {
+    args.iter()
+        .fold(String::new(), |acc, arg| join_path_text(&acc, arg.text()))
+}
+
+fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || is_windows_drive_prefix(path)
+}
+
+/// Normalizes text the way `PurePath` prints it: no trailing separator and
+/// `.` for the empty path.
+fn normalize_path(text: &str) -> String {
+    let trimmed = text.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        return if text.is_empty() { "." } else { "/" }.to_string();
+    }
+    trimmed.strip_prefix("./").unwrap_or(trimmed).to_string()
+}
+
+fn parent_path(path: &str) -> String {
+    let path = normalize_path(path);
+    match path.rfind(['/', '\\']) {
+        Some(0) => "/".to_string(),
+        Some(idx) => path[..idx].to_string(),
+        None if path == "/" => path,
+        None => ".".to_string(),
+    }
+}
+
+pub fn is_path_class(name: &str) -> bool {
+    let class = name.strip_prefix("pathlib.").unwrap_or(name);
+    matches!(
+        class,
+        "Path" | "PurePath" | "PosixPath" | "WindowsPath" | "PurePosixPath" | "PureWindowsPath"
+    )
+}
+
+/// The function name within `os.path` (or `posixpath`, `ntpath`, or `path`
+/// after `from os import path`) that `name` refers to.
+fn os_path_function(name: &str) -> Option<&str> {
+    ["os.path.", "posixpath.", "ntpath.", "path."]
+        .iter()
+        .find_map(|module| name.strip_prefix(module))
+}
+
+pub fn is_join_function(name: &str) -> bool {
+    os_path_function(name) == Some("join")
+}
+
+/// `os.path.dirname`: everything before the last separator.
+fn dirname(path: &str) -> String {
+    match path.rfind(['/', '\\']) {
+        Some(idx) => {
+            let head = path[..idx].trim_end_matches(['/', '\\']);
+            if head.is_empty() {
+                path[..=idx].to_string()
+            } else {
+                head.to_string()
+            }
+        }
+        None => String::new(),
+    }
+}
+
+/// Removes `.` components and folds `..` into the component before it, as
+/// `os.path.normpath` does.
+fn collapse_dots(path: &str) -> String {
+    let absolute = path.starts_with(['/', '\\']);
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." if parts.last().is_some_and(|last| *last != "..") => {
+                parts.pop();
+            }
+            ".." if absolute => {}
+            _ => parts.push(part),
+        }
+    }
+    let joined = parts.join("/");
+    match (absolute, joined.is_empty()) {
+        (true, _) => format!("/{joined}"),
+        (false, true) => ".".to_string(),
+        (false, false) => joined,
+    }
+}
+
+fn is_name_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80
+}
+
+/// Recursive-descent evaluator for the expressions that commonly build
+/// paths: string literals and f-strings, names, `/` and `+`, calls,
+/// attributes and parentheses.
+struct Parser<'a> {
+    text: &'a str,
+    strings: Vec<StringToken>,
+    pos: usize,
+    bindings: &'a Bindings,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, bindings: &'a Bindings) -> Self {
+        Self {
+            text,
+            strings: tokenize_strings(text),
+            pos: 0,
+            bindings,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_trivia(&mut self) {
+        let bytes = self.text.as_bytes();
+        while self.pos < bytes.len() {
+            match bytes[self.pos] {
+                b' ' | b'\t' | b'\r' | b'\n' | b'\x0c' => self.pos += 1,
+                b'\\' => self.pos += 1,
+                b'#' => {
+                    while self.pos < bytes.len() && bytes[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_trivia();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    /// `sum := product ('+' product)*`
+    fn expression(&mut self) -> Option<Term> {
+        let mut left = self.product()?;
+        while self.eat(b'+') {
+            let right = self.product()?;
+            left = match (left, right) {
+                (Term::Value(Value::Str(a)), Term::Value(Value::Str(b))) => {
+                    Term::Value(Value::Str(a + &b))
+                }
+                _ => return None,
+            };
+        }
+        Some(left)
+    }
+
+    /// `product := term ('/' term)*`
+    fn product(&mut self) -> Option<Term> {
+        let mut left = self.term()?;
+        loop {
+            self.skip_trivia();
+            if self.peek() != Some(b'/') || self.text[self.pos..].starts_with("//") {
+                return Some(left);
+            }
+            self.pos += 1;
+            let right = self.term()?;
+            left = match (left, right) {
+                (Term::Value(a @ Value::Path(_)), Term::Value(b))
+                | (Term::Value(a @ Value::Str(_)), Term::Value(b @ Value::Path(_))) => Term::Value(
+                    Value::Path(normalize_path(&join_path_text(a.text(), b.text()))),
+                ),
+                _ => return None,
+            };
+        }
+    }
+
+    /// `term := atom ('.' NAME | '(' args ')' | '[' INT ']')*`
+    fn term(&mut self) -> Option<Term> {
+        let mut term = self.atom()?;
+        loop {
+            if self.eat(b'.') {
+                let name = self.name()?;
+                term = attribute(term, name)?;
+            } else if self.eat(b'(') {
+                let args = self.arguments()?;
+                term = call(term, &args)?;
+            } else if self.eat(b'[') {
+                let index = self.integer()?;
+                if !self.eat(b']') {
+                    return None;
+                }
+                let Term::Parents(path) = term else {
+                    return None;
+                };
+                // Stop at the root or `.` so a huge index does not spin.
+                let mut ancestor = parent_path(&path);
+                for _ in 0..index {
+                    let parent = parent_path(&ancestor);
+                    if parent == ancestor {
+                        break;
+                    }
+                    ancestor = parent;
+                }
+                term = Term::Value(Value::Path(ancestor));
+            } else {
+                return Some(term);
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Option<usize> {
+        self.skip_trivia();
+        let digits = self.text[self.pos..]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+        let value = self.text[self.pos..self.pos + digits].parse().ok()?;
+        self.pos += digits;
+        Some(value)
+    }
+
+    fn atom(&mut self) -> Option<Term> {
+        self.skip_trivia();
+        if self.eat(b'(') {
+            let inner = self.expression()?;
+            return self.eat(b')').then_some(inner);
+        }
+        if let Some(value) = self.strings()? {
+            return Some(Term::Value(Value::Str(value)));
+        }
+        let name = self.name()?;
+        if name == "__file__" {
+            let file = self.bindings.file.as_ref()?;
+            return Some(Term::Value(Value::Str(
+                file.to_string_lossy().replace('\\', "/"),
+            )));
+        }
+        Some(match self.bindings.get(name) {
+            Some(value) => Term::Value(value.clone()),
+            None => Term::Name(name.to_string()),
+        })
+    }
+
+    /// Reads implicitly concatenated string literals at the cursor, returning
+    /// `Some(None)` when there are none and `None` when one cannot be
+    /// evaluated.
+    fn strings(&mut self) -> Option<Option<String>> {
+        let idx = self.strings.partition_point(|token| token.start < self.pos);
+        let Some(first) = self.strings.get(idx).filter(|t| t.start == self.pos) else {
+            return Some(None);
+        };
+        if !first.valid_prefix {
+            return None;
+        }
+        let mut value = String::new();
+        let mut idx = idx;
+        loop {
+            let token = &self.strings[idx];
+            if !token.terminated {
+                return None;
+            }
+            let raw = &self.text[token.content_start..token.content_end];
+            if token.is_fstring {
+                value.push_str(&self.fstring_value(raw, token)?);
+            } else {
+                value.push_str(&token.kind().unescape(raw));
+            }
+            self.pos = token.end();
+            idx += 1;
+            match self.strings.get(idx) {
+                Some(next) if next.continues_previous => {}
+                _ => return Some(Some(value)),
+            }
+        }
+    }
+
+    /// Evaluates an f-string whose replacement fields are all plain
+    /// expressions without conversions or format specs.
+    fn fstring_value(&self, raw: &str, token: &StringToken) -> Option<String> {
+        let kind = token.kind();
+        let mut value = String::new();
+        let mut rest = raw;
+        while let Some(open) = rest.find(['{', '}']) {
+            value.push_str(&kind.unescape(&rest[..open]));
+            let brace = rest.as_bytes()[open];
+            if rest.as_bytes().get(open + 1) == Some(&brace) {
+                value.push(brace as char);
+                rest = &rest[open + 2..];
+                continue;
+            }
+            if brace == b'}' {
+                return None;
+            }
+            let close = open + rest[open..].find('}')?;
+            let field = &rest[open + 1..close];
+            if field.contains([':', '!', '{']) {
+                return None;
+            }
+            value.push_str(evaluate(field, self.bindings)?.text());
+            rest = &rest[close + 1..];
+        }
+        value.push_str(&kind.unescape(rest));
+        Some(value)
+    }
+
+    fn name(&mut self) -> Option<&'a str> {
+        self.skip_trivia();
+        let start = self.pos;
+        let bytes = self.text.as_bytes();
+        if bytes.get(start).is_none_or(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        while self.pos < bytes.len() && is_name_byte(bytes[self.pos]) {
+            self.pos += 1;
+        }
+        (self.pos > start).then(|| &self.text[start..self.pos])
+    }
+
+    /// Parses call arguments up to the closing parenthesis. Keyword arguments
+    /// are skipped; positional ones must all evaluate.
+    fn arguments(&mut self) -> Option<Vec<Value>> {
+        let mut args = Vec::new();
+        loop {
+            if self.eat(b')') {
+                return Some(args);
+            }
+            let checkpoint = self.pos;
+            let keyword = self.name().is_some() && self.eat(b'=') && !self.eat(b'=');
+            if !keyword {
+                self.pos = checkpoint;
+            }
+            match self.expression()? {
+                Term::Value(value) if !keyword => args.push(value),
+                _ if keyword => {}
+                _ => return None,
+            }
+            if !self.eat(b',') {
+                return self.eat(b')').then_some(args);
+            }
+        }
+    }
+}
+
+fn call(callee: Term, args: &[Value]) -> Option<Term> {
+    let value = match callee {
+        Term::Name(name) if is_path_class(&name) => Value::Path(normalize_path(&join_all(args))),
+        Term::Name(name) if is_join_function(&name) => Value::Str(join_all(args)),
+        Term::Name(name) => {
+            let [arg] = args else {
+                return None;
+            };
+            let arg = arg.text();
+            match (name.as_str(), os_path_function(&name)) {
+                ("str" | "os.fspath", _) => Value::Str(arg.to_string()),
+                (_, Some("dirname")) => Value::Str(dirname(arg)),
+                (_, Some("abspath" | "realpath" | "normpath")) => Value::Str(collapse_dots(arg)),
+                _ => return None,
+            }
+        }
+        Term::Method(Value::Path(path), method)
+            if args.is_empty() && matches!(method.as_str(), "resolve" | "absolute") =>
+        {
+            Value::Path(normalize_path(&collapse_dots(&path)))
+        }
+        Term::Method(Value::Path(base), method) if method == "joinpath" => {
+            Value::Path(normalize_path(
+                &args
+                    .iter()
+                    .fold(base, |acc, arg| join_path_text(&acc, arg.text())),
+            ))
+        }
+        _ => return None,
+    };
+    Some(Term::Value(value))
+}
+
+fn attribute(term: Term, name: &str) -> Option<Term> {
+    match term {
+        Term::Name(base) => Some(Term::Name(format!("{base}.{name}"))),
+        Term::Value(Value::Path(path)) if name == "parent" => {
+            Some(Term::Value(Value::Path(parent_path(&path))))
+        }
+        Term::Value(Value::Path(path)) if name == "parents" => Some(Term::Parents(path)),
+        Term::Value(value @ Value::Path(_)) => Some(Term::Method(value, name.to_string())),
+        _ => None,
+    }
+}
+
+/// Offset of the newline ending the logical line that starts at `start`,
+/// following brackets, strings and backslash continuations.
+fn logical_line_end(text: &str, tokens: &[StringToken], start: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut next_token = tokens.partition_point(|token| token.start < start);
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < bytes.len() {
+        if let Some(token) = tokens.get(next_token).filter(|t| t.start == i) {
+            i = token.end();
+            next_token += 1;
+            continue;
+        }
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'\\' => i += 1,
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => depth = depth.saturating_sub(1),
+            b'\n' if depth == 0 => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Walks backwards over document text, stepping over string literals as
+/// whole units.
+struct Scanner<'a> {
+    text: &'a str,
+    tokens: &'a [StringToken],
+}
+
+impl Scanner<'_> {
+    /// The string token ending exactly at `end`.
+    fn string_ending_at(&self, end: usize) -> Option<&StringToken> {
+        let idx = self.tokens.partition_point(|token| token.end() < end);
+        self.tokens
+            .get(idx)
+            .filter(|token| token.end() == end && token.terminated)
+    }
+
+    fn skip_space_back(&self, mut pos: usize) -> usize {
+        let bytes = self.text.as_bytes();
+        while pos > 0 && matches!(bytes[pos - 1], b' ' | b'\t' | b'\r' | b'\n' | b'\\') {
+            pos -= 1;
+        }
+        pos
+    }
+
+    /// Offset of the bracket matching the closing one at `close`.
+    fn matching_open(&self, close: usize) -> Option<usize> {
+        let bytes = self.text.as_bytes();
+        let mut depth = 0usize;
+        let mut pos = close + 1;
+        while pos > 0 {
+            if let Some(token) = self.string_ending_at(pos) {
+                pos = token.start;
+                continue;
+            }
+            pos -= 1;
+            match bytes[pos] {
+                b')' | b']' | b'}' => depth += 1,
+                b'(' | b'[' | b'{' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(pos);
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Finds the call whose argument list contains the comma at `comma`,
+    /// returning its opening parenthesis and the top-level commas before
+    /// `comma`, in order.
+    fn enclosing_call(&self, comma: usize) -> Option<(usize, Vec<usize>)> {
+        let bytes = self.text.as_bytes();
+        let mut commas = Vec::new();
+        let mut depth = 0usize;
+        let mut pos = comma;
+        while pos > 0 {
+            if let Some(token) = self.string_ending_at(pos) {
+                pos = token.start;
+                continue;
+            }
+            pos -= 1;
+            match bytes[pos] {
+                b')' | b']' | b'}' => depth += 1,
+                b'(' if depth == 0 => {
+                    commas.reverse();
+                    return Some((pos, commas));
+                }
+                b'[' | b'{' if depth == 0 => return None,
+                b'(' | b'[' | b'{' => depth -= 1,
+                b',' if depth == 0 => commas.push(pos),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Start of the primary expression (name, call, attribute access,
+    /// subscript, string or parenthesized expression) ending at `end`.
+    fn primary_start(&self, end: usize) -> Option<usize> {
+        let bytes = self.text.as_bytes();
+        let mut pos = self.skip_space_back(end);
+        let mut consumed = false;
+        loop {
+            if let Some(token) = self.string_ending_at(pos) {
+                return Some(token.start);
+            }
+            match bytes[..pos].last() {
+                Some(b')' | b']') => {
+                    pos = self.matching_open(pos - 1)?;
+                    consumed = true;
+                    let trailer = bytes[..pos]
+                        .last()
+                        .is_some_and(|b| is_name_byte(*b) || matches!(b, b')' | b']'))
+                        || self.string_ending_at(pos).is_some();
+                    if !trailer {
+                        return Some(pos);
+                    }
+                }
+                Some(b) if is_name_byte(*b) => {
+                    while pos > 0 && is_name_byte(bytes[pos - 1]) {
+                        pos -= 1;
+                    }
+                    consumed = true;
+                    match bytes[..pos].last() {
+                        Some(b'.') => pos -= 1,
+                        _ => return Some(pos),
+                    }
+                }
+                _ => return consumed.then_some(pos),
+            }
+        }
+    }
+
+    /// Start of the `/`-joined chain of primaries ending at `end`, which is
+    /// the left operand of a following `/`.
+    fn operand_start(&self, end: usize) -> Option<usize> {
+        let mut start = self.primary_start(end)?;
+        loop {
+            let before = self.skip_space_back(start);
+            let joined = self.text[..before].ends_with('/') && !self.text[..before].ends_with("//");
+            if !joined {
+                return Some(start);
+            }
+            start = self.primary_start(before - 1)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bindings(text: &str) -> Bindings {
+        Bindings::scan(
+            text,
+            &tokenize_strings(text),
+            Some(Path::new("/proj/src/train.py")),
+        )
+    }
+
+    fn base_of(text: &str, needle: &str) -> Option<String> {
+        let tokens = tokenize_strings(text);
+        let start = text.rfind(needle).unwrap();
+        literal_base(text, &tokens, start, &bindings(text))
+    }
+
+    #[test]
+    fn evaluates_module_assignments() {
+        let text = "from pathlib import Path\n\
+            DATA = Path(\"data\")\n\
+            ROOT: Path = Path(__file__).parent\n\
+            BASE = \"./assets\"\n\
+            OUT = f\"{BASE}/out\" \\\n    \"/v1\"\n\
+            RAW = DATA / \"raw\" / \"v2/\"\n\
+            CFG = ROOT.joinpath(\"configs\", \"train\")\n\
+            def main():\n    BASE = \"elsewhere\"\n\
+            UNKNOWN = load()\n";
+        let bindings = bindings(text);
+        assert_eq!(bindings.get("DATA"), Some(&Value::Path("data".into())));
+        assert_eq!(bindings.get("ROOT"), Some(&Value::Path("/proj/src".into())));
+        assert_eq!(bindings.get("BASE"), Some(&Value::Str("./assets".into())));
+        assert_eq!(
+            bindings.get("OUT"),
+            Some(&Value::Str("./assets/out/v1".into()))
+        );
+        assert_eq!(
+            bindings.get("RAW"),
+            Some(&Value::Path("data/raw/v2".into()))
+        );
+        assert_eq!(
+            bindings.get("CFG"),
+            Some(&Value::Path("/proj/src/configs/train".into()))
+        );
+        assert_eq!(bindings.get("UNKNOWN"), None);
+    }
+
+    #[test]
+    fn follows_os_path_helpers() {
+        assert_eq!(dirname("/proj/src/train.py"), "/proj/src");
+        assert_eq!(dirname("/train.py"), "/");
+        assert_eq!(dirname("train.py"), "");
+        assert_eq!(collapse_dots("/proj/src/../data/./x"), "/proj/data/x");
+        assert_eq!(collapse_dots("../a/.."), "..");
+        assert_eq!(parent_path("/proj"), "/");
+    }
+
+    #[test]
+    fn finds_the_base_a_literal_is_joined_onto() {
+        let defs = "DATA = Path(\"data\")\nBASE = \"./assets\"\n";
+        let case = |expr: &str, needle: &str| base_of(&format!("{defs}{expr}"), needle);
+        assert_eq!(case("p = DATA / \"raw/\"", "\"raw/"), Some("data".into()));
+        assert_eq!(
+            case("p = (DATA / \"raw\") / \"x\"", "\"x"),
+            Some("data/raw".into())
+        );
+        assert_eq!(
+            case("p = os.path.join(BASE, \"img/\")", "\"img/"),
+            Some("./assets".into())
+        );
+        assert_eq!(
+            case("p = DATA.joinpath(\"a\", \"b\")", "\"b"),
+            Some("data/a".into())
+        );
+        assert_eq!(
+            case("p = Path(__file__).parent / \"cfg\"", "\"cfg"),
+            Some("/proj/src".into())
+        );
+        assert_eq!(
+            case("p = Path(__file__).resolve().parents[1] / \"cfg\"", "\"cfg"),
+            Some("/proj".into())
+        );
+        assert_eq!(
+            case(
+                "p = Path(__file__).parents[18446744073709551615] / \"cfg\"",
+                "\"cfg"
+            ),
+            Some("/".into())
+        );
+        assert_eq!(
+            case(
+                "p = os.path.join(os.path.dirname(__file__), \"..\", \"x\")",
+                "\"x"
+            ),
+            Some("/proj/src/..".into())
+        );
+        assert_eq!(
+            case(
+                "HERE = os.path.dirname(os.path.abspath(__file__))\np = os.path.join(HERE, \"x\")",
+                "\"x"
+            ),
+            Some("/proj/src".into())
+        );
+        assert_eq!(case("p = other / \"raw\"", "\"raw"), None);
+        assert_eq!(case("p = open(\"raw\")", "\"raw"), None);
+        assert_eq!(case("p = os.path.join(load(), \"x\")", "\"x"), None);
+        assert_eq!(case("p = 10 // \"x\"", "\"x"), None);
+    }
+}
@@ -0,0 +1,385 @@
+use std::path::{Path, PathBuf};
+
+use crate::completion::{fold_for_match, is_case_sensitive, normalize_for_match};
+use crate::config::Config;
+
+/// Upper bound on the patterns produced by brace expansion, so a pathological
+/// pattern such as `{a,b}{c,d}{e,f}...` cannot blow up.
+const MAX_BRACE_EXPANSIONS: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`: any single character except `/`.
+    AnyChar,
+    /// `*`: any run of characters except `/`.
+    Star,
+    /// `**`: any run of characters, including `/`.
+    GlobStar,
+    /// `[a-z]`, `[!abc]`: one character (never `/`) in or out of the ranges.
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+/// A compiled glob pattern. Brace alternation is expanded at compile time, so
+/// matching only walks flat token lists.
+#[derive(Debug, Clone)]
+pub struct Glob {
+    alternatives: Vec<Vec<Token>>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let alternatives = expand_braces(pattern)
+            .iter()
+            .map(|expanded| tokenize(expanded))
+            .collect();
+        Self { alternatives }
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        self.alternatives
+            .iter()
+            .any(|tokens| match_tokens(tokens, &chars))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SetPattern {
+    glob: Glob,
+    negated: bool,
+    anchored: bool,
+    case_sensitive: bool,
+}
+
+/// The `ignore_globs` list, compiled once per configuration.
+///
+/// Patterns are tried in order and the last match wins, so `!pattern`
+/// re-includes paths excluded by an earlier pattern. Patterns starting with
+/// `./` are matched against the path relative to the workspace root instead of
+/// the absolute path.
+#[derive(Debug, Clone, Default)]
+pub struct GlobSet {
+    patterns: Vec<SetPattern>,
+    root: Option<PathBuf>,
+}
+
+impl GlobSet {
+    pub fn new(patterns: &[String], config: &Config, root: Option<&Path>) -> Self {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                let (negated, pattern) = match pattern.strip_prefix('!') {
+                    Some(rest) => (true, rest),
+                    None => (false, pattern.as_str()),
+                };
+                let (anchored, pattern) = match pattern.strip_prefix("./") {
+                    Some(rest) => (true, rest),
+                    None => (false, pattern),
+                };
+                let case_sensitive = is_case_sensitive(pattern, config);
+                SetPattern {
+                    glob: Glob::new(&fold_for_match(pattern, case_sensitive)),
+                    negated,
+                    anchored,
+                    case_sensitive,
+                }
+            })
+            .collect();
+        Self {
+            patterns,
+            root: root.map(Path::to_path_buf),
+        }
+    }
+
+    /// Whether `path` is excluded. With `as_dir`, the path is matched with a
+    /// trailing `/`, so `**/name/**` also excludes the directory itself.
+    pub fn is_match(&self, path: &Path, as_dir: bool) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let suffix = if as_dir { "/" } else { "" };
+        let absolute = format!("{}{suffix}", normalize_for_match(path));
+        let relative = self
+            .root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root).ok())
+            .map(|rel| format!("{}{suffix}", normalize_for_match(rel)));
+        let absolute = [
+            fold_for_match(&absolute, false),
+            fold_for_match(&absolute, true),
+        ];
+        let relative =
+            relative.map(|rel| [fold_for_match(&rel, false), fold_for_match(&rel, true)]);
+
+        let mut excluded = false;
+        for pattern in &self.patterns {
+            let variants = if pattern.anchored {
+                match &relative {
+                    Some(relative) => relative,
+                    None => continue,
+                }
+            } else {
+                &absolute
+            };
+            if pattern
+                .glob
+                .is_match(&variants[usize::from(pattern.case_sensitive)])
+            {
+                excluded = !pattern.negated;
+            }
+        }
+        excluded
+    }
+}
+
+/// Expands the first top-level `{a,b}` group and recurses on the results.
+/// Unbalanced braces are left in place and match literally.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let mut expanded = Vec::new();
+    expand_into(pattern, &mut expanded);
+    expanded
+}
+
+fn expand_into(pattern: &str, out: &mut Vec<String>) {
+    if out.len() >= MAX_BRACE_EXPANSIONS {
+        return;
+    }
+    let Some((open, close, commas)) = find_brace_group(pattern) else {
+        out.push(pattern.to_string());
+        return;
+    };
+    let prefix = &pattern[..open];
+    let suffix = &pattern[close + 1..];
+    let mut start = open + 1;
+    for end in commas.into_iter().chain(std::iter::once(close)) {
+        let alternative = &pattern[start..end];
+        expand_into(&format!("{prefix}{alternative}{suffix}"), out);
+        start = end + 1;
+    }
+}
+
+/// Locates the first balanced, unescaped brace group that contains at least
+/// one top-level comma, returning its byte offsets.
+fn find_brace_group(pattern: &str) -> Option<(usize, usize, Vec<usize>)> {
+    let bytes = pattern.as_bytes();
+    let mut i = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'{' => {
+                let mut depth = 0usize;
+                let mut commas = Vec::new();
+                let mut j = i;
+                while j < bytes.len() {
+                    match bytes[j] {
+                        b'\\' => j += 1,
+                        b'{' => depth += 1,
+                        b'}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        b',' if depth == 1 => commas.push(j),
+                        _ => {}
+                    }
+                    j += 1;
+                }
+                if j < bytes.len() && !commas.is_empty() {
+                    return Some((i, j, commas));
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    None
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0usize;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                tokens.push(Token::Char(chars[i + 1]));
+                i += 2;
+            }
+            '*' => {
+                let mut run = 0usize;
+                while i < chars.len() && chars[i] == '*' {
+                    run += 1;
+                    i += 1;
+                }
+                tokens.push(if run > 1 {
+                    Token::GlobStar
+                } else {
+                    Token::Star
+                });
+            }
+            '?' => {
+                tokens.push(Token::AnyChar);
+                i += 1;
+            }
+            '[' => match parse_class(&chars, i) {
+                Some((token, next)) => {
+                    tokens.push(token);
+                    i = next;
+                }
+                None => {
+                    tokens.push(Token::Char('['));
+                    i += 1;
+                }
+            },
+            ch => {
+                tokens.push(Token::Char(ch));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Parses a character class starting at `chars[start] == '['`, returning the
+/// token and the index just past the closing `]`. An unclosed class is `None`.
+fn parse_class(chars: &[char], start: usize) -> Option<(Token, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(chars.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let mut ranges = Vec::new();
+    let mut first = true;
+    while i < chars.len() {
+        let mut ch = chars[i];
+        if ch == ']' && !first {
+            return Some((Token::Class { negated, ranges }, i + 1));
+        }
+        first = false;
+        if ch == '\\' && i + 1 < chars.len() {
+            i += 1;
+            ch = chars[i];
+        }
+        if chars.get(i + 1) == Some(&'-') && chars.get(i + 2).is_some_and(|c| *c != ']') {
+            let mut end = chars[i + 2];
+            let mut next = i + 3;
+            if end == '\\' && next < chars.len() {
+                end = chars[next];
+                next += 1;
+            }
+            ranges.push((ch, end));
+            i = next;
+        } else {
+            ranges.push((ch, ch));
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Runs the tokens over `text` with a single row of reachable positions per
+/// token, so matching is linear in `tokens * text` with no backtracking.
+fn match_tokens(tokens: &[Token], text: &[char]) -> bool {
+    let mut reachable = vec![false; text.len() + 1];
+    reachable[0] = true;
+    for token in tokens {
+        let mut next = vec![false; text.len() + 1];
+        match token {
+            Token::Star | Token::GlobStar => {
+                let crosses_separators = *token == Token::GlobStar;
+                for j in 0..=text.len() {
+                    next[j] = reachable[j]
+                        || (j > 0 && next[j - 1] && (crosses_separators || text[j - 1] != '/'));
+                }
+            }
+            _ => {
+                for j in 0..text.len() {
+                    if reachable[j] && matches_one(token, text[j]) {
+                        next[j + 1] = true;
+                    }
+                }
+            }
+        }
+        if !next.iter().any(|r| *r) {
+            return false;
+        }
+        reachable = next;
+    }
+    reachable[text.len()]
+}
+
+fn matches_one(token: &Token, ch: char) -> bool {
+    match token {
+        Token::Char(expected) => *expected == ch,
+        Token::AnyChar => ch != '/',
+        Token::Class { negated, ranges } => {
+            ch != '/' && ranges.iter().any(|(lo, hi)| (*lo..=*hi).contains(&ch)) != *negated
+        }
+        Token::Star | Token::GlobStar => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MatchCase;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        Glob::new(pattern).is_match(text)
+    }
+
+    #[test]
+    fn glob_match_basic() {
+        assert!(matches("**/node_modules/**", "/proj/node_modules/pkg"));
+        assert!(matches("**/.git/**", "/proj/.git/config"));
+        assert!(!matches("**/.venv/**", "/proj/src/main.py"));
+        assert!(!matches("*.py", "src/main.py"));
+    }
+
+    #[test]
+    fn supports_classes_braces_and_escapes() {
+        assert!(matches("**/run-?/*.ckpt", "/proj/run-3/last.ckpt"));
+        assert!(!matches("**/run-?/*.ckpt", "/proj/run-10/last.ckpt"));
+        assert!(matches("**/shard-[0-9][0-9].bin", "/d/shard-07.bin"));
+        assert!(!matches("**/shard-[!0-9]*", "/d/shard-07.bin"));
+        assert!(matches("**/*.{csv,tsv}", "/d/train.tsv"));
+        assert!(!matches("**/*.{csv,tsv}", "/d/train.json"));
+        assert!(matches("**/{a,b{c,d}}.txt", "/x/bd.txt"));
+        assert!(matches("**/\\[draft\\]*", "/x/[draft] notes"));
+        assert!(matches("**/{unclosed", "/x/{unclosed"));
+    }
+
+    #[test]
+    fn set_applies_negation_and_anchoring() {
+        let config = Config::default();
+        let patterns = vec![
+            "**/checkpoints/**".to_string(),
+            "!**/checkpoints/best.pt".to_string(),
+            "./outputs/**".to_string(),
+        ];
+        let set = GlobSet::new(&patterns, &config, Some(Path::new("/proj")));
+        assert!(set.is_match(Path::new("/proj/checkpoints/last.pt"), false));
+        assert!(!set.is_match(Path::new("/proj/checkpoints/best.pt"), false));
+        assert!(set.is_match(Path::new("/proj/outputs/log.txt"), false));
+        assert!(!set.is_match(Path::new("/proj/src/outputs/log.txt"), false));
+        assert!(set.is_match(Path::new("/proj/outputs"), true));
+    }
+
+    #[test]
+    fn ignore_globs_follow_case_policy() {
+        let patterns = vec!["**/build/**".to_string()];
+        let insensitive = Config {
+            match_case: MatchCase::Insensitive,
+            ..Config::default()
+        };
+        let set = GlobSet::new(&patterns, &insensitive, None);
+        assert!(set.is_match(Path::new("/proj/Build/out"), false));
+        let set = GlobSet::new(&patterns, &Config::default(), None);
+        assert!(!set.is_match(Path::new("/proj/Build/out"), false));
+    }
+}
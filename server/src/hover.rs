@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use crate::cache::{list_dir_entries, DirCache};
+use crate::completion::filter_entries;
+use crate::config::Config;
+use crate::context::PathExpectation;
+use crate::ignore::GitIgnore;
+use crate::metadata::{fenced, text_preview, PathKind, PathMetadata};
+
+/// Builds the hover contents for a literal resolving to `paths`, one section
+/// per candidate.
+pub fn hover_markdown(paths: &[PathBuf], cache: &mut DirCache, config: &Config) -> String {
+    paths
+        .iter()
+        .map(|path| path_markdown(path, cache, config))
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Describes a single path: its metadata followed by a file preview or a
+/// directory listing.
+pub fn path_markdown(path: &Path, cache: &mut DirCache, config: &Config) -> String {
+    let meta = PathMetadata::read(path);
+    let mut section = format!("**`{}`**\n\n{}", path.display(), meta.markdown_facts());
+    match meta.kind {
+        PathKind::File if meta.exists => {
+            if let Some(preview) = text_preview(path, config.preview_lines) {
+                section.push_str("\n\n");
+                section.push_str(&fenced(&preview, ""));
+            }
+        }
+        PathKind::Directory => {
+            if let Some(listing) = directory_listing(path, cache, config) {
+                section.push_str("\n\n");
+                section.push_str(&fenced(&listing, ""));
+            }
+        }
+        _ => {}
+    }
+    section
+}
+
+pub fn directory_listing(path: &Path, cache: &mut DirCache, config: &Config) -> Option<String> {
+    if config.preview_lines == 0 {
+        return None;
+    }
+    let entries = list_dir_entries(path, cache, config)?;
+    let gitignore = config
+        .respect_gitignore
+        .then(|| GitIgnore::for_dir(path, None));
+    let filtered = filter_entries(
+        entries,
+        "",
+        config,
+        gitignore.as_ref(),
+        &PathExpectation::default(),
+    );
+    if filtered.is_empty() {
+        return Some("(empty)".into());
+    }
+    let mut lines: Vec<String> = filtered
+        .iter()
+        .take(config.preview_lines)
+        .map(|(name, is_dir, _)| {
+            if *is_dir {
+                format!("{name}/")
+            } else {
+                name.clone()
+            }
+        })
+        .collect();
+    if filtered.len() > config.preview_lines {
+        lines.push(format!("… {} more", filtered.len() - config.preview_lines));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn hover_shows_listing_for_directory() {
+        let root = TempDir::new("hover");
+        std::fs::create_dir_all(root.join("data").join("raw")).unwrap();
+        std::fs::write(root.join("data").join("a.csv"), "x,y\n1,2\n").unwrap();
+
+        let mut cache = DirCache::new(Duration::from_millis(0), 8);
+        let config = Config::default();
+        let dir_md = hover_markdown(&[root.join("data")], &mut cache, &config);
+        let file_md = hover_markdown(&[root.join("data").join("a.csv")], &mut cache, &config);
+
+        assert!(dir_md.contains("- type: directory"));
+        assert!(dir_md.contains("raw/\na.csv"));
+        assert!(file_md.contains("- size: 8 B (8 bytes)"));
+        assert!(file_md.contains("x,y\n1,2"));
+    }
+
+    #[test]
+    fn hover_reports_missing_path() {
+        let mut cache = DirCache::new(Duration::from_millis(0), 8);
+        let md = hover_markdown(
+            &[PathBuf::from("/nonexistent-pathy/file.txt")],
+            &mut cache,
+            &Config::default(),
+        );
+        assert!(md.contains("- exists: no"));
+    }
+}
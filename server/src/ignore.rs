@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::completion::dirs_home;
+use crate::glob::Glob;
+
+const IGNORE_FILE_NAMES: [&str; 2] = [".gitignore", ".ignore"];
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    pattern: Glob,
+    /// For anchored `**/x` patterns, `x` alone, which also matches at the base.
+    base_pattern: Option<Glob>,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    /// Parses one line of a gitignore file, or `None` for blanks and comments.
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let line = trim_unescaped_trailing_spaces(line);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, rest) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // `\#` and `\!` escape the leading special characters.
+        let rest = rest
+            .strip_prefix('\\')
+            .filter(|r| r.starts_with(['#', '!']))
+            .unwrap_or(rest);
+        let (dir_only, rest) = match rest.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, rest),
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        let anchored = rest.contains('/');
+        let pattern = rest.strip_prefix('/').unwrap_or(rest);
+        Some(Self {
+            pattern: Glob::new(pattern),
+            base_pattern: pattern.strip_prefix("**/").map(Glob::new),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, rel: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if !self.anchored {
+            let name = rel.rsplit('/').next().unwrap_or(rel);
+            return self.pattern.is_match(name);
+        }
+        self.pattern.is_match(rel)
+            || self
+                .base_pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(rel))
+    }
+}
+
+#[derive(Debug)]
+struct IgnoreFile {
+    source: PathBuf,
+    base: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreFile {
+    fn read(path: &Path, base: &Path) -> Option<Self> {
+        let text = std::fs::read_to_string(path).ok()?;
+        let rules: Vec<IgnoreRule> = text.lines().filter_map(IgnoreRule::parse).collect();
+        if rules.is_empty() {
+            return None;
+        }
+        Some(Self {
+            source: path.to_path_buf(),
+            base: base.to_path_buf(),
+            rules,
+        })
+    }
+}
+
+/// Gitignore-style rules that apply to one directory: the global excludes
+/// file, `.git/info/exclude`, and every `.gitignore` / `.ignore` from the top
+/// of the hierarchy down to the directory. Later layers take precedence, and
+/// within a layer the last matching rule wins.
+#[derive(Debug, Clone, Default)]
+pub struct GitIgnore {
+    layers: Vec<Arc<IgnoreFile>>,
+}
+
+impl GitIgnore {
+    /// Loads the rules that apply to entries of `dir`. Ignore files are read
+    /// from `root_dir` (or the enclosing git repository when `dir` lies outside
+    /// it) down to `dir`.
+    pub fn for_dir(dir: &Path, root_dir: Option<&Path>) -> Self {
+        let repo_root = find_repo_root(dir);
+        let top = match (root_dir, &repo_root) {
+            (Some(root), _) if dir.starts_with(root) => root.to_path_buf(),
+            (_, Some(repo)) => repo.clone(),
+            _ => dir.to_path_buf(),
+        };
+        let mut ignore = Self::repository(repo_root.as_deref().unwrap_or(&top));
+        let mut current = top.clone();
+        ignore = ignore.with_dir(&current);
+        if let Ok(rel) = dir.strip_prefix(&top) {
+            for component in rel.components() {
+                current.push(component);
+                ignore = ignore.with_dir(&current);
+            }
+        }
+        ignore
+    }
+
+    /// Combines the rules for several listed directories. Layers are ordered
+    /// by depth so deeper ignore files keep their precedence.
+    fn merge(sets: impl IntoIterator<Item = Self>) -> Self {
+        let mut layers: Vec<Arc<IgnoreFile>> = Vec::new();
+        for set in sets {
+            for layer in set.layers {
+                if layers.iter().all(|known| known.source != layer.source) {
+                    layers.push(layer);
+                }
+            }
+        }
+        layers.sort_by_key(|layer| layer.base.components().count());
+        Self { layers }
+    }
+
+    /// Rules that apply to the whole repository rooted at `repo_root`.
+    pub fn repository(repo_root: &Path) -> Self {
+        let mut layers = Vec::new();
+        if let Some(global) = global_excludes_file() {
+            layers.extend(IgnoreFile::read(&global, repo_root).map(Arc::new));
+        }
+        let exclude = repo_root.join(".git").join("info").join("exclude");
+        layers.extend(IgnoreFile::read(&exclude, repo_root).map(Arc::new));
+        Self { layers }
+    }
+
+    /// Extends these rules with the ignore files found in `dir`.
+    pub fn with_dir(&self, dir: &Path) -> Self {
+        let mut layers = self.layers.clone();
+        for name in IGNORE_FILE_NAMES {
+            layers.extend(IgnoreFile::read(&dir.join(name), dir).map(Arc::new));
+        }
+        Self { layers }
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        for layer in self.layers.iter().rev() {
+            let Ok(rel) = path.strip_prefix(&layer.base) else {
+                continue;
+            };
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            if rel.is_empty() {
+                continue;
+            }
+            if let Some(rule) = layer.rules.iter().rev().find(|r| r.matches(&rel, is_dir)) {
+                return !rule.negated;
+            }
+        }
+        false
+    }
+}
+
+/// Parsed [`GitIgnore`] rules per listed directory, kept until an ignore file
+/// or the workspace root changes.
+#[derive(Debug, Default)]
+pub struct IgnoreCache {
+    dirs: HashMap<PathBuf, GitIgnore>,
+}
+
+impl IgnoreCache {
+    pub fn for_dirs(&mut self, dirs: &[PathBuf], root_dir: Option<&Path>) -> GitIgnore {
+        let sets: Vec<GitIgnore> = dirs
+            .iter()
+            .map(|dir| {
+                self.dirs
+                    .entry(dir.clone())
+                    .or_insert_with(|| GitIgnore::for_dir(dir, root_dir))
+                    .clone()
+            })
+            .collect();
+        GitIgnore::merge(sets)
+    }
+
+    pub fn clear(&mut self) {
+        self.dirs.clear();
+    }
+}
+
+/// Whether `path` holds rules that [`GitIgnore`] reads: a `.gitignore` or
+/// `.ignore`, a repository's `.git/info/exclude`, or the global excludes file.
+pub fn is_ignore_file(path: &Path) -> bool {
+    if path
+        .file_name()
+        .is_some_and(|name| IGNORE_FILE_NAMES.iter().any(|known| name == *known))
+    {
+        return true;
+    }
+    path.ends_with(Path::new(".git").join("info").join("exclude"))
+        || global_excludes_file().is_some_and(|global| global == path)
+}
+
+fn trim_unescaped_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while line[..end].ends_with(' ') && !line[..end - 1].ends_with('\\') {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+fn find_repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Locates `core.excludesFile` from the user's git config, falling back to
+/// git's default of `$XDG_CONFIG_HOME/git/ignore`.
+pub fn global_excludes_file() -> Option<PathBuf> {
+    let home = dirs_home();
+    let xdg_config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+    let mut config_files = Vec::new();
+    if let Some(home) = &home {
+        config_files.push(home.join(".gitconfig"));
+    }
+    if let Some(xdg) = &xdg_config {
+        config_files.push(xdg.join("git").join("config"));
+    }
+    for config_file in config_files {
+        let Ok(text) = std::fs::read_to_string(&config_file) else {
+            continue;
+        };
+        if let Some(value) = read_excludes_setting(&text) {
+            return Some(match (value.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => home.join(rest),
+                _ => PathBuf::from(value),
+            });
+        }
+    }
+    xdg_config.map(|xdg| xdg.join("git").join("ignore"))
+}
+
+fn read_excludes_setting(git_config: &str) -> Option<String> {
+    let mut in_core = false;
+    for line in git_config.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_core = line
+                .trim_matches(['[', ']'])
+                .trim()
+                .eq_ignore_ascii_case("core");
+            continue;
+        }
+        if !in_core {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if key.trim().eq_ignore_ascii_case("excludesfile") {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn parses_rule_flags() {
+        let rule = IgnoreRule::parse("!/build/").unwrap();
+        assert!(rule.negated && rule.dir_only && rule.anchored);
+        assert!(rule.pattern.is_match("build"));
+        assert!(IgnoreRule::parse("# comment").is_none());
+        assert!(IgnoreRule::parse("\\#literal")
+            .unwrap()
+            .pattern
+            .is_match("#literal"));
+        assert!(!IgnoreRule::parse("*.ckpt").unwrap().anchored);
+    }
+
+    #[test]
+    fn applies_hierarchy_negation_and_anchoring() {
+        let root = TempDir::new("ignore");
+        let sub = root.join("runs");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(root.join(".gitignore"), "*.ckpt\n/outputs/\n").unwrap();
+        std::fs::write(sub.join(".ignore"), "!best.ckpt\n").unwrap();
+
+        let ignore = GitIgnore::for_dir(&sub, Some(&root));
+        let top = GitIgnore::for_dir(&root, Some(&root));
+
+        assert!(ignore.is_ignored(&sub.join("last.ckpt"), false));
+        assert!(!ignore.is_ignored(&sub.join("best.ckpt"), false));
+        assert!(!ignore.is_ignored(&sub.join("config.yaml"), false));
+        assert!(top.is_ignored(&root.join("outputs"), true));
+        assert!(!top.is_ignored(&root.join("outputs"), false));
+        assert!(!ignore.is_ignored(&sub.join("outputs"), true));
+    }
+
+    #[test]
+    fn caches_rules_until_cleared() {
+        let root = TempDir::new("ignore-cache");
+        std::fs::write(root.join(".gitignore"), "*.ckpt\n").unwrap();
+        let dirs = [root.to_path_buf()];
+        let mut cache = IgnoreCache::default();
+
+        assert!(cache
+            .for_dirs(&dirs, Some(&root))
+            .is_ignored(&root.join("last.ckpt"), false));
+        std::fs::write(root.join(".gitignore"), "*.log\n").unwrap();
+        assert!(cache
+            .for_dirs(&dirs, Some(&root))
+            .is_ignored(&root.join("last.ckpt"), false));
+        cache.clear();
+        let ignore = cache.for_dirs(&dirs, Some(&root));
+
+        assert!(!ignore.is_ignored(&root.join("last.ckpt"), false));
+        assert!(ignore.is_ignored(&root.join("run.log"), false));
+        assert!(is_ignore_file(&root.join(".gitignore")));
+        assert!(is_ignore_file(
+            &root.join(".git").join("info").join("exclude")
+        ));
+        assert!(!is_ignore_file(&root.join("exclude")));
+    }
+
+    #[test]
+    fn reads_excludes_file_from_git_config() {
+        let config = "[user]\n\tname = x\n[core]\n\texcludesFile = ~/.gitignore_global\n";
+        assert_eq!(
+            read_excludes_setting(config).as_deref(),
+            Some("~/.gitignore_global")
+        );
+        assert!(read_excludes_setting("[user]\nexcludesfile = nope\n").is_none());
+    }
+}
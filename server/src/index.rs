@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::completion::{fold_for_match, fuzzy_score, is_case_sensitive};
+use crate::config::{Config, MatchingStrategy};
+use crate::ignore::GitIgnore;
+
+#[derive(Debug, Default)]
+struct IndexedFiles {
+    /// Workspace-relative paths using `/` separators.
+    files: BTreeSet<String>,
+    complete: bool,
+}
+
+/// In-memory list of every file under the workspace root, built on a
+/// background thread and kept fresh from `workspace/didChangeWatchedFiles`.
+#[derive(Debug)]
+pub struct WorkspaceIndex {
+    root: PathBuf,
+    ignore_globs: Vec<String>,
+    respect_gitignore: bool,
+    max_files: usize,
+    files: Arc<Mutex<IndexedFiles>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl WorkspaceIndex {
+    pub fn spawn(root: PathBuf, config: &Config) -> Self {
+        let files = Arc::new(Mutex::new(IndexedFiles::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let index = Self {
+            root: root.clone(),
+            ignore_globs: config.ignore_globs.clone(),
+            respect_gitignore: config.respect_gitignore,
+            max_files: config.workspace_index_max_files,
+            files: Arc::clone(&files),
+            cancel: Arc::clone(&cancel),
+        };
+        let config = config.clone();
+        std::thread::spawn(move || {
+            let mut found = Vec::new();
+            walk(&root, &root, &config, &mut found, &cancel);
+            if cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            if let Ok(mut indexed) = files.lock() {
+                indexed.files.extend(found);
+                indexed.complete = true;
+            }
+        });
+        index
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Whether the index was built with settings equivalent to `config`.
+    pub fn matches_config(&self, root: &Path, config: &Config) -> bool {
+        self.root == root
+            && self.ignore_globs == config.ignore_globs
+            && self.respect_gitignore == config.respect_gitignore
+            && self.max_files == config.workspace_index_max_files
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.files.lock().map(|f| f.complete).unwrap_or(false)
+    }
+
+    /// Finds indexed files whose name matches `segment`, best matches first.
+    pub fn search(&self, segment: &str, config: &Config, limit: usize) -> Vec<String> {
+        let Ok(indexed) = self.files.lock() else {
+            return Vec::new();
+        };
+        let case_sensitive = is_case_sensitive(segment, config);
+        let folded_segment = fold_for_match(segment, case_sensitive);
+        let mut scored: Vec<(i64, &String)> = indexed
+            .files
+            .iter()
+            .filter(|rel| config.show_hidden || !rel.split('/').any(|c| c.starts_with('.')))
+            .filter_map(|rel| {
+                let name = rel.rsplit('/').next().unwrap_or(rel);
+                let score = match config.matching {
+                    MatchingStrategy::Prefix => fold_for_match(name, case_sensitive)
+                        .starts_with(&folded_segment)
+                        .then_some(0),
+                    MatchingStrategy::Fuzzy => fuzzy_score(segment, name, case_sensitive),
+                }?;
+                Some((score, rel))
+            })
+            .collect();
+        scored.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.len().cmp(&b.len()))
+                .then_with(|| a.cmp(b))
+        });
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(_, rel)| rel.clone())
+            .collect()
+    }
+
+    pub fn file_created(&self, path: &Path, config: &Config) {
+        if !path.starts_with(&self.root) {
+            return;
+        }
+        let mut found = Vec::new();
+        if path.is_dir() {
+            walk(&self.root, path, config, &mut found, &self.cancel);
+        } else if !is_ignored(path, false, config, self.gitignore_for(path).as_ref()) {
+            found.extend(relative_key(&self.root, path));
+        }
+        if let Ok(mut indexed) = self.files.lock() {
+            let room = self.max_files.saturating_sub(indexed.files.len());
+            indexed.files.extend(found.into_iter().take(room));
+        }
+    }
+
+    fn gitignore_for(&self, path: &Path) -> Option<GitIgnore> {
+        let dir = path.parent()?;
+        self.respect_gitignore
+            .then(|| GitIgnore::for_dir(dir, Some(&self.root)))
+    }
+
+    pub fn file_deleted(&self, path: &Path) {
+        let Some(key) = relative_key(&self.root, path) else {
+            return;
+        };
+        if let Ok(mut indexed) = self.files.lock() {
+            let dir_prefix = format!("{key}/");
+            indexed
+                .files
+                .retain(|rel| rel != &key && !rel.starts_with(&dir_prefix));
+        }
+    }
+}
+
+impl Drop for WorkspaceIndex {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn walk(root: &Path, start: &Path, config: &Config, found: &mut Vec<String>, cancel: &AtomicBool) {
+    let gitignore = config
+        .respect_gitignore
+        .then(|| GitIgnore::for_dir(start, Some(root)));
+    let mut stack = vec![(start.to_path_buf(), gitignore)];
+    while let Some((dir, gitignore)) = stack.pop() {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in read_dir.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            // Symlinked directories are not followed to avoid cycles.
+            if file_type.is_dir() {
+                if !is_ignored(&path, true, config, gitignore.as_ref()) {
+                    let nested = gitignore.as_ref().map(|rules| rules.with_dir(&path));
+                    stack.push((path, nested));
+                }
+                continue;
+            }
+            if is_ignored(&path, false, config, gitignore.as_ref()) {
+                continue;
+            }
+            if let Some(key) = relative_key(root, &path) {
+                found.push(key);
+                if found.len() >= config.workspace_index_max_files {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+fn is_ignored(path: &Path, is_dir: bool, config: &Config, gitignore: Option<&GitIgnore>) -> bool {
+    if gitignore.is_some_and(|rules| rules.is_ignored(path, is_dir)) {
+        return true;
+    }
+    // `**/name/**` should exclude the directory itself, not just its children.
+    config.ignore_set.is_match(path, is_dir)
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    if parts.is_empty() {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+/// Expresses `target` relative to `base`, climbing with `..` as needed.
+pub fn relative_path_from(base: &Path, target: &Path) -> Option<String> {
+    let base: Vec<Component> = base.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = base
+        .iter()
+        .zip(target.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let mut parts: Vec<String> = vec!["..".to_string(); base.len() - common];
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().to_string()),
+    );
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::time::{Duration, Instant};
+
+    fn wait_until_complete(index: &WorkspaceIndex) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !index.is_complete() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn indexes_files_and_skips_ignored_dirs() {
+        let root = TempDir::new("index");
+        std::fs::create_dir_all(root.join("configs").join("experiments")).unwrap();
+        std::fs::create_dir_all(root.join("node_modules").join("pkg")).unwrap();
+        std::fs::write(root.join("configs/experiments/train.yaml"), "").unwrap();
+        std::fs::write(root.join("node_modules/pkg/train.yaml"), "").unwrap();
+
+        let config = Config::default();
+        let index = WorkspaceIndex::spawn(root.to_path_buf(), &config);
+        wait_until_complete(&index);
+        let found = index.search("train", &config, 10);
+
+        std::fs::write(root.join("train_extra.yaml"), "").unwrap();
+        index.file_created(&root.join("train_extra.yaml"), &config);
+        index.file_deleted(&root.join("configs"));
+        let after = index.search("train", &config, 10);
+
+        assert_eq!(found, vec!["configs/experiments/train.yaml".to_string()]);
+        assert_eq!(after, vec!["train_extra.yaml".to_string()]);
+    }
+
+    #[test]
+    fn computes_relative_paths() {
+        let base = Path::new("/proj/src/pkg");
+        let target = Path::new("/proj/configs/train.yaml");
+        assert_eq!(
+            relative_path_from(base, target).as_deref(),
+            Some("../../configs/train.yaml")
+        );
+        assert_eq!(
+            relative_path_from(Path::new("/proj"), target).as_deref(),
+            Some("configs/train.yaml")
+        );
+    }
+}
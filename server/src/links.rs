@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use lsp_types::{DocumentLink, Url};
+
+use crate::completion::{find_string_literals, resolve_literal_paths};
+use crate::config::Config;
+use crate::eval::{join_path_text, literal_base, Bindings};
+use crate::tokenizer::StringToken;
+
+pub fn document_links(
+    text: &str,
+    tokens: &[StringToken],
+    bindings: &Bindings,
+    file_dir: Option<&Path>,
+    root_dir: Option<&Path>,
+    config: &Config,
+) -> Vec<DocumentLink> {
+    let mut links = Vec::new();
+    for literal in find_string_literals(text, tokens) {
+        let content = literal.content.as_str();
+        if content.trim().is_empty() || (literal.is_fstring && content.contains('{')) {
+            continue;
+        }
+        let joined = literal_base(text, tokens, literal.string_start_byte, bindings)
+            .map(|base| join_path_text(&base, content));
+        let content = joined.as_deref().unwrap_or(content);
+        let Some(path) = resolve_literal_paths(content, file_dir, root_dir, config)
+            .into_iter()
+            .find(|path| path.exists())
+        else {
+            continue;
+        };
+        let Ok(target) = Url::from_file_path(&path) else {
+            continue;
+        };
+        links.push(DocumentLink {
+            range: literal.content_range,
+            target: Some(target),
+            tooltip: Some(path.to_string_lossy().to_string()),
+            data: None,
+        });
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use crate::tokenizer::tokenize_strings;
+    use lsp_types::Position;
+
+    #[test]
+    fn links_existing_paths_only() {
+        let root = TempDir::new("links");
+        std::fs::create_dir_all(root.join("configs")).unwrap();
+        std::fs::write(root.join("configs").join("train.yaml"), "").unwrap();
+
+        let text = "cfg = open(\"configs/train.yaml\")\nother = \"configs/missing.yaml\"\n";
+        let tokens = tokenize_strings(text);
+        let bindings = Bindings::default();
+        let links = document_links(
+            text,
+            &tokens,
+            &bindings,
+            Some(&root),
+            None,
+            &Config::default(),
+        );
+
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].range.start, Position::new(0, 12));
+        let target = links[0].target.as_ref().unwrap();
+        assert!(target.path().ends_with("configs/train.yaml"));
+    }
+}
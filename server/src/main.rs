@@ -1,43 +1,145 @@
+mod actions;
 mod cache;
 mod completion;
 mod config;
 mod context;
+mod diagnostics;
+mod eval;
+mod glob;
+mod hover;
+mod ignore;
+mod index;
+mod links;
+mod metadata;
+mod notebook;
+mod signatures;
+#[cfg(test)]
+mod testutil;
+mod tokenizer;
 
+use std::cell::OnceCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
-use cache::{DirCache, DirEntryInfo};
+use actions::{code_actions, execute_create_path, ActionContext, CREATE_PATH_COMMAND};
+use cache::{list_dir_entries, DirCache};
 use completion::{
-    base_dir_from_uri, build_relative_query, filter_entries, find_prefix_query, find_string_info,
-    resolve_list_dirs, segment_start_offset, separator_for_insertion, utf16_len,
+    base_dir_from_uri, build_relative_query, dirs_home, filter_entries, find_prefix_query,
+    find_string_info, find_string_literal_at, resolve_list_dirs, resolve_literal_paths,
+    separator_for_insertion, PathQuery, PrefixKind,
+};
+use config::{
+    load_config, BaseDirStrategy, Config, ContextGating, DiagnosticsSeverity, MatchingStrategy,
+    PathFunctionRule, StatStrategy, WorkspaceRootStrategy,
 };
-use config::{load_config, Config, ContextGating};
-use context::is_path_context;
+use context::{is_path_context, path_expectation};
+use diagnostics::{find_missing_paths, missing_path_diagnostic, MissingPath};
+use eval::{join_path_text, literal_base, Bindings};
+use hover::{hover_markdown, path_markdown};
+use ignore::IgnoreCache;
+use index::{relative_path_from, WorkspaceIndex};
+use links::document_links;
 use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
 use lsp_types::{
-    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
-    ConfigurationItem, ConfigurationParams, InitializeParams, InitializeResult, Position, Range,
-    ServerCapabilities, TextDocumentContentChangeEvent, TextDocumentItem,
-    TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+    CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability, CompletionItem,
+    CompletionItemKind, CompletionList, CompletionOptions, CompletionParams, CompletionResponse,
+    ConfigurationItem, ConfigurationParams, DeclarationCapability,
+    DidChangeWatchedFilesRegistrationOptions, DocumentLink, DocumentLinkOptions,
+    DocumentLinkParams, Documentation, ExecuteCommandOptions, ExecuteCommandParams, FileChangeType,
+    FileEvent, FileSystemWatcher, GlobPattern, GotoDefinitionParams, GotoDefinitionResponse, Hover,
+    HoverContents, HoverParams, HoverProviderCapability, InitializeParams, InitializeResult,
+    Location, MarkupContent, MarkupKind, OneOf, Position, PublishDiagnosticsParams, Range,
+    Registration, RegistrationParams, ServerCapabilities, TextDocumentContentChangeEvent,
+    TextDocumentItem, TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url,
+};
+use metadata::PathMetadata;
+use notebook::{
+    CellSpan, DidChangeNotebookDocumentParams, DidCloseNotebookDocumentParams,
+    DidOpenNotebookDocumentParams, Notebook,
 };
+use signatures::SignatureCache;
+use tokenizer::{tokenize_strings, StringToken};
 
 #[derive(Debug, Clone)]
 struct DocumentState {
     text: String,
     language_id: Option<String>,
+    /// String tokens for `text`, computed on first use after each change.
+    strings: OnceCell<Vec<StringToken>>,
+    /// Module-level path bindings, computed like `strings`.
+    bindings: OnceCell<Bindings>,
+    /// Rules for the workspace functions the document defines or imports,
+    /// also dropped when a watched file changes.
+    signatures: OnceCell<Vec<PathFunctionRule>>,
+    /// Missing paths found by the last published diagnostics, dropped when
+    /// the text or the filesystem changes.
+    missing: Option<Vec<MissingPath>>,
+    /// Set for the combined source of a notebook, which has no `__file__`.
+    is_notebook: bool,
+}
+
+impl DocumentState {
+    fn new(text: String, language_id: Option<String>) -> Self {
+        Self {
+            text,
+            language_id,
+            strings: OnceCell::new(),
+            bindings: OnceCell::new(),
+            signatures: OnceCell::new(),
+            missing: None,
+            is_notebook: false,
+        }
+    }
+
+    fn notebook(text: String) -> Self {
+        Self {
+            is_notebook: true,
+            ..Self::new(text, Some("python".into()))
+        }
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = text;
+        self.strings = OnceCell::new();
+        self.bindings = OnceCell::new();
+        self.signatures = OnceCell::new();
+        self.missing = None;
+    }
+
+    fn strings(&self) -> &[StringToken] {
+        self.strings.get_or_init(|| tokenize_strings(&self.text))
+    }
+
+    /// Bindings for the document at `uri`, whose path is `__file__` unless
+    /// it is a notebook.
+    fn bindings(&self, uri: &Url) -> &Bindings {
+        self.bindings.get_or_init(|| {
+            let file = uri.to_file_path().ok().filter(|_| !self.is_notebook);
+            Bindings::scan(&self.text, self.strings(), file.as_deref())
+        })
+    }
 }
 
 #[derive(Debug)]
 struct ServerState {
     documents: HashMap<Url, DocumentState>,
+    /// Open notebooks. Each is also in `documents` under its own URI, holding
+    /// the combined source of its Python cells.
+    notebooks: HashMap<Url, Notebook>,
     root_uri: Option<Url>,
     cache: DirCache,
     config: Config,
     config_warned: bool,
     debug: bool,
     pending_config_request: Option<RequestId>,
+    pending_diagnostics: HashMap<Url, Instant>,
     next_request_id: i32,
+    index: Option<WorkspaceIndex>,
+    signatures: SignatureCache,
+    ignore_cache: IgnoreCache,
+    watch_files_supported: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -46,17 +148,29 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let (initialize_id, initialize_params) = connection.initialize_start()?;
     let initialize_params: InitializeParams = serde_json::from_value(initialize_params)?;
 
+    #[allow(deprecated)]
+    let root_uri = initialize_params.root_uri.clone();
+    let root_dir = root_uri.as_ref().and_then(|uri| uri.to_file_path().ok());
     let mut config_warned = false;
-    let mut config = Config::default();
-    if let Some(options) = initialize_params.initialization_options.as_ref() {
-        config = load_config(options, &mut config_warned);
-    }
+    let options = initialize_params
+        .initialization_options
+        .clone()
+        .unwrap_or_default();
+    let config = load_config(&options, &mut config_warned, root_dir.as_deref());
 
     let debug = std::env::var_os("PATHY_DEBUG").is_some();
+    let watch_files_supported = initialize_params
+        .capabilities
+        .workspace
+        .as_ref()
+        .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+        .and_then(|watched| watched.dynamic_registration)
+        .unwrap_or(false);
 
     let mut state = ServerState {
         documents: HashMap::new(),
-        root_uri: initialize_params.root_uri.clone(),
+        notebooks: HashMap::new(),
+        root_uri,
         cache: DirCache::new(
             Duration::from_millis(config.cache_ttl_ms),
             config.cache_max_dirs,
@@ -65,16 +179,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config_warned,
         debug,
         pending_config_request: None,
+        pending_diagnostics: HashMap::new(),
         next_request_id: 1,
+        index: None,
+        signatures: SignatureCache::default(),
+        ignore_cache: IgnoreCache::default(),
+        watch_files_supported,
     };
+    refresh_workspace_index(&mut state);
 
     let capabilities = ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
         completion_provider: Some(CompletionOptions {
             trigger_characters: Some(vec!["/".into(), "\\".into(), "~".into(), ".".into()]),
-            resolve_provider: Some(false),
+            resolve_provider: Some(true),
             ..CompletionOptions::default()
         }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        declaration_provider: Some(DeclarationCapability::Simple(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        document_link_provider: Some(DocumentLinkOptions {
+            resolve_provider: Some(false),
+            work_done_progress_options: Default::default(),
+        }),
+        execute_command_provider: Some(ExecuteCommandOptions {
+            commands: vec![CREATE_PATH_COMMAND.into()],
+            ..ExecuteCommandOptions::default()
+        }),
         ..ServerCapabilities::default()
     };
 
@@ -86,9 +218,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }),
     };
 
-    connection.initialize_finish(initialize_id, serde_json::to_value(initialize_result)?)?;
+    // lsp-types has no notebook capability, so it is added to the JSON.
+    let mut initialize_result = serde_json::to_value(initialize_result)?;
+    initialize_result["capabilities"]["notebookDocumentSync"] = notebook::sync_options();
+    connection.initialize_finish(initialize_id, initialize_result)?;
 
-    for message in &connection.receiver {
+    loop {
+        let message = match next_diagnostics_timeout(&state) {
+            Some(timeout) => match connection.receiver.recv_timeout(timeout) {
+                Ok(message) => message,
+                Err(err) if err.is_timeout() => {
+                    publish_due_diagnostics(&connection, &mut state);
+                    continue;
+                }
+                Err(_) => break,
+            },
+            None => match connection.receiver.recv() {
+                Ok(message) => message,
+                Err(_) => break,
+            },
+        };
         match message {
             Message::Request(request) => {
                 if connection.handle_shutdown(&request)? {
@@ -125,25 +274,80 @@ fn handle_notification(
                     language_id,
                     ..
                 } = params.text_document;
-                state.documents.insert(
-                    uri,
-                    DocumentState {
-                        text,
-                        language_id: Some(language_id),
-                    },
-                );
+                state
+                    .documents
+                    .insert(uri.clone(), DocumentState::new(text, Some(language_id)));
+                schedule_diagnostics(state, uri);
             }
         }
         "textDocument/didChange" => {
             if let Ok(params) = serde_json::from_value::<lsp_types::DidChangeTextDocumentParams>(
                 notification.params.clone(),
             ) {
-                if let Some(doc) = state.documents.get_mut(&params.text_document.uri) {
+                let uri = params.text_document.uri;
+                if let Some(doc) = state.documents.get_mut(&uri) {
                     if let Some(TextDocumentContentChangeEvent { text, .. }) =
                         params.content_changes.last().cloned()
                     {
-                        doc.text = text;
+                        doc.set_text(text);
                     }
+                    schedule_diagnostics(state, uri);
+                }
+            }
+        }
+        "textDocument/didClose" => {
+            if let Ok(params) = serde_json::from_value::<lsp_types::DidCloseTextDocumentParams>(
+                notification.params.clone(),
+            ) {
+                let uri = params.text_document.uri;
+                state.documents.remove(&uri);
+                state.pending_diagnostics.remove(&uri);
+                send_diagnostics(connection, uri, Vec::new());
+            }
+        }
+        "notebookDocument/didOpen" => {
+            if let Ok(params) =
+                serde_json::from_value::<DidOpenNotebookDocumentParams>(notification.params.clone())
+            {
+                let uri = params.notebook_document.uri.clone();
+                let mut notebook = Notebook::open(params);
+                let source = notebook.source();
+                state.notebooks.insert(uri.clone(), notebook);
+                state
+                    .documents
+                    .insert(uri.clone(), DocumentState::notebook(source));
+                schedule_diagnostics(state, uri);
+            }
+        }
+        "notebookDocument/didChange" => {
+            if let Ok(params) = serde_json::from_value::<DidChangeNotebookDocumentParams>(
+                notification.params.clone(),
+            ) {
+                let uri = params.notebook_document.uri;
+                let Some(notebook) = state.notebooks.get_mut(&uri) else {
+                    return;
+                };
+                let closed = notebook.apply(params.change);
+                let source = notebook.source();
+                if let Some(doc) = state.documents.get_mut(&uri) {
+                    doc.set_text(source);
+                }
+                for cell in closed {
+                    send_diagnostics(connection, cell, Vec::new());
+                }
+                schedule_diagnostics(state, uri);
+            }
+        }
+        "notebookDocument/didClose" => {
+            if let Ok(params) = serde_json::from_value::<DidCloseNotebookDocumentParams>(
+                notification.params.clone(),
+            ) {
+                let uri = params.notebook_document.uri;
+                state.notebooks.remove(&uri);
+                state.documents.remove(&uri);
+                state.pending_diagnostics.remove(&uri);
+                for cell in params.cell_text_documents {
+                    send_diagnostics(connection, cell.uri, Vec::new());
                 }
             }
         }
@@ -154,8 +358,16 @@ fn handle_notification(
                 apply_config_update(state, &params.settings);
             }
         }
+        "workspace/didChangeWatchedFiles" => {
+            if let Ok(params) = serde_json::from_value::<lsp_types::DidChangeWatchedFilesParams>(
+                notification.params.clone(),
+            ) {
+                apply_file_changes(state, params.changes);
+            }
+        }
         "initialized" => {
             request_workspace_config(connection, state);
+            register_file_watcher(connection, state);
         }
         "exit" => {
             std::process::exit(0);
@@ -197,38 +409,326 @@ fn request_workspace_config(connection: &Connection, state: &mut ServerState) {
     connection.sender.send(Message::Request(request)).ok();
 }
 
+fn register_file_watcher(connection: &Connection, state: &mut ServerState) {
+    if !state.watch_files_supported {
+        return;
+    }
+    let id = RequestId::from(state.next_request_id);
+    state.next_request_id += 1;
+
+    let mut watchers = vec![FileSystemWatcher {
+        glob_pattern: GlobPattern::String("**/*".into()),
+        kind: None,
+    }];
+    // The global excludes file lives outside the workspace.
+    if let Some(global) = ignore::global_excludes_file() {
+        watchers.push(FileSystemWatcher {
+            glob_pattern: GlobPattern::String(global.to_string_lossy().into_owned()),
+            kind: None,
+        });
+    }
+    let options = DidChangeWatchedFilesRegistrationOptions { watchers };
+    let params = RegistrationParams {
+        registrations: vec![Registration {
+            id: "pathy-watched-files".into(),
+            method: "workspace/didChangeWatchedFiles".into(),
+            register_options: serde_json::to_value(options).ok(),
+        }],
+    };
+    let request = Request::new(id, "client/registerCapability".into(), params);
+    connection.sender.send(Message::Request(request)).ok();
+}
+
 fn apply_config_update(state: &mut ServerState, value: &serde_json::Value) {
-    let new_config = load_config(value, &mut state.config_warned);
+    let root_dir = workspace_root_dir(state);
+    let new_config = load_config(value, &mut state.config_warned, root_dir.as_deref());
     state.cache.update_limits(
         Duration::from_millis(new_config.cache_ttl_ms),
         new_config.cache_max_dirs,
     );
     state.config = new_config;
+    state.ignore_cache.clear();
+    for doc in state.documents.values_mut() {
+        doc.missing = None;
+    }
+    refresh_workspace_index(state);
+    let uris: Vec<Url> = state.documents.keys().cloned().collect();
+    for uri in uris {
+        schedule_diagnostics(state, uri);
+    }
     if state.debug {
         eprintln!("pathy-server: config updated");
     }
 }
 
+/// Starts, rebuilds or drops the workspace index to match the current config.
+fn refresh_workspace_index(state: &mut ServerState) {
+    let root = match state.config.workspace_root_strategy {
+        WorkspaceRootStrategy::LspRootUri if state.config.workspace_index => {
+            workspace_root_dir(state)
+        }
+        _ => None,
+    };
+    let Some(root) = root else {
+        state.index = None;
+        return;
+    };
+    if let Some(index) = &state.index {
+        if index.matches_config(&root, &state.config) {
+            return;
+        }
+    }
+    log_debug(state, "indexing workspace");
+    state.index = Some(WorkspaceIndex::spawn(root, &state.config));
+}
+
+fn apply_file_changes(state: &mut ServerState, changes: Vec<FileEvent>) {
+    for change in changes {
+        let Ok(path) = change.uri.to_file_path() else {
+            continue;
+        };
+        if let Some(parent) = path.parent() {
+            state.cache.invalidate(parent);
+        }
+        state.signatures.invalidate(&path);
+        let is_ignore_file = ignore::is_ignore_file(&path);
+        if is_ignore_file {
+            state.ignore_cache.clear();
+        }
+        if state.config.respect_gitignore && is_ignore_file {
+            // Ignore rules changed underneath the index; rebuild it.
+            state.index = None;
+            refresh_workspace_index(state);
+            continue;
+        }
+        let Some(index) = &state.index else {
+            continue;
+        };
+        if change.typ == FileChangeType::CREATED {
+            index.file_created(&path, &state.config);
+        } else if change.typ == FileChangeType::DELETED {
+            index.file_deleted(&path);
+        }
+    }
+    // Imported modules may have changed, and paths may have appeared.
+    for doc in state.documents.values_mut() {
+        doc.signatures = OnceCell::new();
+        doc.missing = None;
+    }
+    let uris: Vec<Url> = state.documents.keys().cloned().collect();
+    for uri in uris {
+        schedule_diagnostics(state, uri);
+    }
+}
+
+fn schedule_diagnostics(state: &mut ServerState, uri: Url) {
+    let deadline = Instant::now() + Duration::from_millis(state.config.diagnostics_debounce_ms);
+    state.pending_diagnostics.insert(uri, deadline);
+}
+
+fn next_diagnostics_timeout(state: &ServerState) -> Option<Duration> {
+    let deadline = state.pending_diagnostics.values().min()?;
+    Some(deadline.saturating_duration_since(Instant::now()))
+}
+
+fn publish_due_diagnostics(connection: &Connection, state: &mut ServerState) {
+    let now = Instant::now();
+    let due: Vec<Url> = state
+        .pending_diagnostics
+        .iter()
+        .filter(|(_, deadline)| **deadline <= now)
+        .map(|(uri, _)| uri.clone())
+        .collect();
+    for uri in due {
+        state.pending_diagnostics.remove(&uri);
+        let diagnostics = document_diagnostics(state, &uri);
+        match state.notebooks.get(&uri) {
+            Some(notebook) => {
+                for (cell, diagnostics) in notebook.cell_diagnostics(&diagnostics) {
+                    send_diagnostics(connection, cell, diagnostics);
+                }
+            }
+            None => send_diagnostics(connection, uri, diagnostics),
+        }
+    }
+}
+
+fn document_diagnostics(state: &mut ServerState, uri: &Url) -> Vec<lsp_types::Diagnostic> {
+    if state.config.diagnostics_severity == DiagnosticsSeverity::Off {
+        return Vec::new();
+    }
+    let missing = document_missing_paths(state, uri);
+    let diagnostics = missing
+        .iter()
+        .filter_map(|missing| missing_path_diagnostic(missing, &state.config))
+        .collect();
+    if let Some(doc) = state.documents.get_mut(uri) {
+        doc.missing = Some(missing);
+    }
+    diagnostics
+}
+
+/// Literals of the document at `uri` in a path context whose target does
+/// not exist.
+fn document_missing_paths(state: &mut ServerState, uri: &Url) -> Vec<MissingPath> {
+    if !state.config.enable {
+        return Vec::new();
+    }
+    let signatures = document_signatures(state, uri);
+    let Some(doc) = state.documents.get(uri) else {
+        return Vec::new();
+    };
+    if !is_python_document(uri, doc.language_id.as_deref()) {
+        return Vec::new();
+    }
+    let file_dir = document_dir(state, uri);
+    let root_dir = workspace_root_dir(state);
+    find_missing_paths(
+        &doc.text,
+        doc.strings(),
+        doc.bindings(uri),
+        &signatures,
+        file_dir.as_deref(),
+        root_dir.as_deref(),
+        &state.config,
+    )
+}
+
+/// Workspace functions with path parameters that the document at `uri`
+/// defines or imports.
+fn document_signatures(state: &mut ServerState, uri: &Url) -> Vec<PathFunctionRule> {
+    let root_dir = workspace_root_dir(state);
+    let Some(doc) = state.documents.get(uri) else {
+        return Vec::new();
+    };
+    if let Some(rules) = doc.signatures.get() {
+        return rules.clone();
+    }
+    let file = uri.to_file_path().ok();
+    let rules = state.signatures.rules_for(
+        &doc.text,
+        doc.strings(),
+        file.as_deref(),
+        root_dir.as_deref(),
+    );
+    doc.signatures.set(rules.clone()).ok();
+    rules
+}
+
+fn send_diagnostics(connection: &Connection, uri: Url, diagnostics: Vec<lsp_types::Diagnostic>) {
+    let params = PublishDiagnosticsParams {
+        uri,
+        diagnostics,
+        version: None,
+    };
+    let notification = Notification::new("textDocument/publishDiagnostics".into(), params);
+    connection
+        .sender
+        .send(Message::Notification(notification))
+        .ok();
+}
+
 fn handle_request(connection: &Connection, state: &mut ServerState, request: &Request) {
     match request.method.as_str() {
         "textDocument/completion" => {
-            let params = match serde_json::from_value::<CompletionParams>(request.params.clone()) {
-                Ok(params) => params,
-                Err(_) => {
-                    let response = Response::new_err(
-                        request.id.clone(),
-                        lsp_server::ErrorCode::InvalidParams as i32,
-                        "Invalid completion params".into(),
-                    );
-                    connection.sender.send(Message::Response(response)).ok();
-                    return;
-                }
+            let Ok(mut params) = serde_json::from_value::<CompletionParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid completion params");
+                return;
             };
-            let items = completion_items(state, params);
-            let result = CompletionResponse::Array(items);
+            let cell = redirect_cell_position(state, &mut params.text_document_position);
+            let mut list = completion_items(state, params);
+            if let Some(cell) = cell {
+                cell.completions_to_cell(&mut list);
+            }
+            let result = CompletionResponse::List(list);
             let response = Response::new_ok(request.id.clone(), result);
             connection.sender.send(Message::Response(response)).ok();
         }
+        "completionItem/resolve" => {
+            let Ok(item) = serde_json::from_value::<CompletionItem>(request.params.clone()) else {
+                respond_invalid_params(connection, request, "Invalid completion item");
+                return;
+            };
+            let item = resolve_completion_item(state, item);
+            let response = Response::new_ok(request.id.clone(), item);
+            connection.sender.send(Message::Response(response)).ok();
+        }
+        "textDocument/hover" => {
+            let Ok(mut params) = serde_json::from_value::<HoverParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid hover params");
+                return;
+            };
+            let cell = redirect_cell_position(state, &mut params.text_document_position_params);
+            let mut hover = hover_item(state, params);
+            if let (Some(cell), Some(hover)) = (cell, hover.as_mut()) {
+                hover.range = hover.range.and_then(|range| cell.to_cell_range(range));
+            }
+            let response = Response::new_ok(request.id.clone(), hover);
+            connection.sender.send(Message::Response(response)).ok();
+        }
+        "textDocument/definition" | "textDocument/declaration" => {
+            let Ok(mut params) =
+                serde_json::from_value::<GotoDefinitionParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid definition params");
+                return;
+            };
+            redirect_cell_position(state, &mut params.text_document_position_params);
+            let locations = definition_locations(state, params);
+            let response = Response::new_ok(request.id.clone(), locations);
+            connection.sender.send(Message::Response(response)).ok();
+        }
+        "textDocument/codeAction" => {
+            let Ok(mut params) = serde_json::from_value::<CodeActionParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid code action params");
+                return;
+            };
+            let cell = redirect_cell(state, &mut params.text_document.uri);
+            if let Some(cell) = &cell {
+                params.range = cell.to_notebook_range(params.range);
+            }
+            let notebook = params.text_document.uri.clone();
+            let mut actions = code_action_items(state, params);
+            if let Some(cell) = cell {
+                cell.actions_to_cell(&notebook, &mut actions);
+            }
+            let response = Response::new_ok(request.id.clone(), actions);
+            connection.sender.send(Message::Response(response)).ok();
+        }
+        "textDocument/documentLink" => {
+            let Ok(mut params) =
+                serde_json::from_value::<DocumentLinkParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid document link params");
+                return;
+            };
+            let cell = redirect_cell(state, &mut params.text_document.uri);
+            let mut links = document_link_items(state, params);
+            if let Some(cell) = cell {
+                links = cell.links_to_cell(links);
+            }
+            let response = Response::new_ok(request.id.clone(), links);
+            connection.sender.send(Message::Response(response)).ok();
+        }
+        "workspace/executeCommand" => {
+            let Ok(params) = serde_json::from_value::<ExecuteCommandParams>(request.params.clone())
+            else {
+                respond_invalid_params(connection, request, "Invalid command params");
+                return;
+            };
+            let response = match execute_command(state, params) {
+                Ok(()) => Response::new_ok(request.id.clone(), serde_json::Value::Null),
+                Err(message) => Response::new_err(
+                    request.id.clone(),
+                    lsp_server::ErrorCode::RequestFailed as i32,
+                    message,
+                ),
+            };
+            connection.sender.send(Message::Response(response)).ok();
+        }
         _ => {
             let response = Response::new_err(
                 request.id.clone(),
@@ -240,68 +740,253 @@ fn handle_request(connection: &Connection, state: &mut ServerState, request: &Re
     }
 }
 
-fn completion_items(state: &mut ServerState, params: CompletionParams) -> Vec<CompletionItem> {
-    if !state.config.enable {
-        return Vec::new();
+fn respond_invalid_params(connection: &Connection, request: &Request, message: &str) {
+    let response = Response::new_err(
+        request.id.clone(),
+        lsp_server::ErrorCode::InvalidParams as i32,
+        message.into(),
+    );
+    connection.sender.send(Message::Response(response)).ok();
+}
+
+fn hover_item(state: &mut ServerState, params: HoverParams) -> Option<Hover> {
+    let (range, paths) = literal_paths_at(state, &params.text_document_position_params)?;
+    let value = hover_markdown(&paths, &mut state.cache, &state.config);
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value,
+        }),
+        range: Some(range),
+    })
+}
+
+fn definition_locations(
+    state: &mut ServerState,
+    params: GotoDefinitionParams,
+) -> Option<GotoDefinitionResponse> {
+    let (_, paths) = literal_paths_at(state, &params.text_document_position_params)?;
+    let mut locations: Vec<Location> = paths
+        .iter()
+        .filter(|path| path.is_file())
+        .filter_map(|path| Url::from_file_path(path).ok())
+        .map(|uri| Location {
+            uri,
+            range: Range::default(),
+        })
+        .collect();
+    match locations.len() {
+        0 => None,
+        1 => locations.pop().map(GotoDefinitionResponse::Scalar),
+        _ => Some(GotoDefinitionResponse::Array(locations)),
     }
+}
 
-    let doc_uri = params.text_document_position.text_document.uri;
-    let position = params.text_document_position.position;
+/// Resolves the closed string literal under the cursor to its candidate paths,
+/// returning the literal's content range alongside them. Literals outside a
+/// path context only resolve when one of their candidates exists.
+fn literal_paths_at(
+    state: &mut ServerState,
+    params: &TextDocumentPositionParams,
+) -> Option<(Range, Vec<PathBuf>)> {
+    if !state.config.enable {
+        return None;
+    }
+    let uri = &params.text_document.uri;
+    let position = params.position;
+    let signatures = document_signatures(state, uri);
+    let doc = state.documents.get(uri)?;
+    if !is_python_document(uri, doc.language_id.as_deref()) {
+        return None;
+    }
+    let offset = document_offset(&doc.text, position)?;
+    let literal = find_string_literal_at(&doc.text, doc.strings(), offset)?;
+    if literal.content.trim().is_empty() || (literal.is_fstring && literal.content.contains('{')) {
+        return None;
+    }
 
-    let doc = match state.documents.get(&doc_uri) {
-        Some(doc) => doc.clone(),
-        None => return Vec::new(),
+    let file_dir = document_dir(state, uri);
+    let root_dir = workspace_root_dir(state);
+    let base = literal_base(
+        &doc.text,
+        doc.strings(),
+        literal.string_start_byte,
+        doc.bindings(uri),
+    );
+    let in_context = base.is_some()
+        || is_path_context(
+            &doc.text,
+            doc.strings(),
+            literal.string_start_byte,
+            &state.config,
+            &signatures,
+        );
+    let path = match base {
+        Some(base) => join_path_text(&base, &literal.content),
+        None => literal.content.clone(),
     };
+    let paths = resolve_literal_paths(
+        &path,
+        file_dir.as_deref(),
+        root_dir.as_deref(),
+        &state.config,
+    );
+    if paths.is_empty() || (!in_context && !paths.iter().any(|path| path.exists())) {
+        return None;
+    }
+    Some((literal.content_range, paths))
+}
 
-    if !is_python_document(&doc_uri, doc.language_id.as_deref()) {
+fn code_action_items(
+    state: &mut ServerState,
+    params: CodeActionParams,
+) -> Vec<CodeActionOrCommand> {
+    if !state.config.enable {
         return Vec::new();
     }
-
-    let line = match get_line(&doc.text, position.line) {
-        Some(line) => line,
-        None => return Vec::new(),
+    let uri = params.text_document.uri;
+    // Reuse what the last published diagnostics found instead of checking
+    // every literal against the filesystem again.
+    let missing = match state
+        .documents
+        .get(&uri)
+        .and_then(|doc| doc.missing.clone())
+    {
+        Some(missing) => missing,
+        None => document_missing_paths(state, &uri),
     };
-
-    let cursor_byte = match utf16_col_to_byte(line, position.character) {
-        Some(idx) => idx,
-        None => return Vec::new(),
+    let file_dir = document_dir(state, &uri);
+    let root_dir = workspace_root_dir(state);
+    let ctx = ActionContext {
+        uri: &uri,
+        file_dir: file_dir.as_deref(),
+        root_dir: root_dir.as_deref(),
+        config: &state.config,
     };
+    code_actions(&ctx, &missing, params.range, &mut state.cache)
+}
 
-    let line_start_offset = match line_start_offset(&doc.text, position.line) {
-        Some(offset) => offset,
-        None => return Vec::new(),
+fn document_link_items(state: &ServerState, params: DocumentLinkParams) -> Vec<DocumentLink> {
+    if !state.config.enable {
+        return Vec::new();
+    }
+    let uri = params.text_document.uri;
+    let Some(doc) = state.documents.get(&uri) else {
+        return Vec::new();
     };
+    if !is_python_document(&uri, doc.language_id.as_deref()) {
+        return Vec::new();
+    }
+    let file_dir = document_dir(state, &uri);
+    let root_dir = workspace_root_dir(state);
+    document_links(
+        &doc.text,
+        doc.strings(),
+        doc.bindings(&uri),
+        file_dir.as_deref(),
+        root_dir.as_deref(),
+        &state.config,
+    )
+}
 
-    let info = match find_string_info(line, cursor_byte) {
-        Some(info) => info,
-        None => return Vec::new(),
-    };
+fn execute_command(state: &mut ServerState, params: ExecuteCommandParams) -> Result<(), String> {
+    if params.command != CREATE_PATH_COMMAND {
+        return Err(format!("unknown command: {}", params.command));
+    }
+    let changed_dir = execute_create_path(&params.arguments)?;
+    state.cache.invalidate(&changed_dir);
+    for doc in state.documents.values_mut() {
+        doc.missing = None;
+    }
+    let uris: Vec<Url> = state.documents.keys().cloned().collect();
+    for uri in uris {
+        schedule_diagnostics(state, uri);
+    }
+    Ok(())
+}
 
-    let string_start_offset = line_start_offset + info.string_start_byte;
+fn completion_items(state: &mut ServerState, params: CompletionParams) -> CompletionList {
+    if !state.config.enable {
+        return CompletionList::default();
+    }
 
-    let prefix_query = if state.config.path_prefix_fallback {
-        find_prefix_query(&info.content_before_cursor, &state.config)
-    } else {
-        None
+    let doc_uri = params.text_document_position.text_document.uri;
+    let position = params.text_document_position.position;
+
+    let info = {
+        let doc = match state.documents.get(&doc_uri) {
+            Some(doc) => doc,
+            None => return CompletionList::default(),
+        };
+        if !is_python_document(&doc_uri, doc.language_id.as_deref()) {
+            return CompletionList::default();
+        }
+        let cursor_offset = match document_offset(&doc.text, position) {
+            Some(offset) => offset,
+            None => return CompletionList::default(),
+        };
+        match find_string_info(&doc.text, doc.strings(), cursor_offset) {
+            Some(info) => info,
+            None => return CompletionList::default(),
+        }
     };
+    let signatures = document_signatures(state, &doc_uri);
 
-    if !is_completion_allowed(
-        state,
-        &doc.text,
-        prefix_query.is_some(),
-        string_start_offset,
-    ) {
-        log_debug(state, "completion gated off");
-        return Vec::new();
-    }
+    // Everything that reads the document is worked out before listing, which
+    // needs the directory cache mutably.
+    let (query, expectation) = {
+        let doc = match state.documents.get(&doc_uri) {
+            Some(doc) => doc,
+            None => return CompletionList::default(),
+        };
+        let string_start_offset = info.string_start_byte;
+        // A literal joined onto an evaluated path, as in `DATA / "raw/"`,
+        // lists relative to that path and is a path context by construction.
+        let base = literal_base(
+            &doc.text,
+            doc.strings(),
+            string_start_offset,
+            doc.bindings(&doc_uri),
+        );
+        let content = match &base {
+            Some(base) => join_path_text(base, &info.content_before_cursor),
+            None => info.content_before_cursor.clone(),
+        };
+
+        let prefix_query = if state.config.path_prefix_fallback {
+            find_prefix_query(&content, &state.config)
+        } else {
+            None
+        };
 
-    let query = prefix_query.unwrap_or_else(|| build_relative_query(&info.content_before_cursor));
+        if base.is_none()
+            && !is_completion_allowed(
+                state,
+                &doc.text,
+                doc.strings(),
+                prefix_query.is_some(),
+                string_start_offset,
+                &signatures,
+            )
+        {
+            log_debug(state, "completion gated off");
+            return CompletionList::default();
+        }
 
-    let file_dir = base_dir_from_uri(&doc_uri, None);
-    let root_dir = state
-        .root_uri
-        .as_ref()
-        .and_then(|uri| uri.to_file_path().ok());
+        let expectation = path_expectation(
+            &doc.text,
+            doc.strings(),
+            string_start_offset,
+            &state.config,
+            &signatures,
+        )
+        .unwrap_or_default();
+        let query = prefix_query.unwrap_or_else(|| build_relative_query(&content));
+        (query, expectation)
+    };
+
+    let file_dir = document_dir(state, &doc_uri);
+    let root_dir = workspace_root_dir(state);
 
     let list_dirs = resolve_list_dirs(
         &query,
@@ -310,9 +995,13 @@ fn completion_items(state: &mut ServerState, params: CompletionParams) -> Vec<Co
         &state.config,
     );
     if list_dirs.is_empty() {
-        return Vec::new();
+        return CompletionList::default();
     }
 
+    let gitignore = state
+        .config
+        .respect_gitignore
+        .then(|| state.ignore_cache.for_dirs(&list_dirs, root_dir.as_deref()));
     let mut entries = Vec::new();
     for dir in list_dirs {
         if let Some(mut listed) = list_dir_entries(&dir, &mut state.cache, &state.config) {
@@ -320,14 +1009,17 @@ fn completion_items(state: &mut ServerState, params: CompletionParams) -> Vec<Co
         }
     }
 
-    let filtered = filter_entries(entries, &query.segment_prefix, &state.config);
-
-    let segment_start_byte = segment_start_offset(&info.content_before_cursor);
-    let segment_start_utf16 = utf16_len(&info.content_before_cursor[..segment_start_byte]);
+    let filtered = filter_entries(
+        entries,
+        &query.segment_prefix,
+        &state.config,
+        gitignore.as_ref(),
+        &expectation,
+    );
 
     let start = Position {
         line: position.line,
-        character: info.string_start_utf16 + segment_start_utf16,
+        character: info.segment_start_utf16,
     };
     let range = Range {
         start,
@@ -336,50 +1028,187 @@ fn completion_items(state: &mut ServerState, params: CompletionParams) -> Vec<Co
 
     let mut seen = std::collections::HashSet::new();
     let mut deduped = Vec::new();
-    for (name, is_dir) in filtered.into_iter().take(state.config.max_results) {
+    for (name, is_dir, path) in filtered {
         if seen.insert(name.clone()) {
-            deduped.push((name, is_dir));
+            deduped.push((name, is_dir, path));
         }
     }
+    if deduped.is_empty()
+        && query.prefix_kind == PrefixKind::Relative
+        && query.dir_part.is_empty()
+        && !query.segment_prefix.is_empty()
+    {
+        return indexed_completion_items(state, &query, file_dir.as_deref(), range, &info);
+    }
+    // Let the editor re-query as more of the segment is typed instead of
+    // filtering a truncated list client-side. Fuzzy results always re-query,
+    // since the editor's own filtering would not reproduce the ranking.
+    let fuzzy = state.config.matching == MatchingStrategy::Fuzzy;
+    let is_incomplete = fuzzy || deduped.len() > state.config.max_results;
+    deduped.truncate(state.config.max_results);
 
-    deduped
-        .into_iter()
-        .map(|(name, is_dir)| completion_item(name, is_dir, range, &state.config, &info))
-        .collect()
+    let mut items = Vec::with_capacity(deduped.len());
+    for (rank, (name, is_dir, path)) in deduped.into_iter().enumerate() {
+        let Some(mut item) = completion_item(name, is_dir, range, &state.config, &info) else {
+            continue;
+        };
+        item.sort_text = Some(format!("{rank:05}"));
+        if fuzzy {
+            item.filter_text = Some(query.segment_prefix.clone());
+        }
+        apply_stat_strategy(&mut item, &path, &mut state.cache, &state.config);
+        items.push(item);
+    }
+    CompletionList {
+        is_incomplete,
+        items,
+    }
+}
+
+/// Offers files from anywhere in the workspace whose name matches a bare
+/// segment that matched nothing in the listing directory.
+fn indexed_completion_items(
+    state: &mut ServerState,
+    query: &PathQuery,
+    file_dir: Option<&std::path::Path>,
+    range: Range,
+    info: &completion::StringInfo,
+) -> CompletionList {
+    if !state.config.include_files {
+        return CompletionList::default();
+    }
+    let Some(index) = &state.index else {
+        return CompletionList::default();
+    };
+    let root = index.root().to_path_buf();
+    let base = match state.config.base_dir {
+        BaseDirStrategy::FileDir => file_dir,
+        BaseDirStrategy::WorkspaceRoot | BaseDirStrategy::Both => Some(root.as_path()),
+    };
+    let Some(base) = base else {
+        return CompletionList::default();
+    };
+    let max_results = state.config.max_results;
+    let mut found = index.search(&query.segment_prefix, &state.config, max_results + 1);
+    // Re-query while the index is still filling in or the list was capped.
+    let is_incomplete = !index.is_complete() || found.len() > max_results;
+    found.truncate(max_results);
+    let sep = separator_for_insertion(&info.content_before_cursor, &state.config);
+
+    let mut items = Vec::with_capacity(found.len());
+    for (rank, rel) in found.into_iter().enumerate() {
+        let path = root.join(&rel);
+        let Some(text) = relative_path_from(base, &path) else {
+            continue;
+        };
+        let text = if sep == '/' {
+            text
+        } else {
+            text.replace('/', &sep.to_string())
+        };
+        let Some(mut item) = completion_item(text, false, range, &state.config, info) else {
+            continue;
+        };
+        item.sort_text = Some(format!("{rank:05}"));
+        item.filter_text = Some(query.segment_prefix.clone());
+        apply_stat_strategy(&mut item, &path, &mut state.cache, &state.config);
+        items.push(item);
+    }
+    CompletionList {
+        is_incomplete,
+        items,
+    }
+}
+
+fn apply_stat_strategy(
+    item: &mut CompletionItem,
+    path: &std::path::Path,
+    cache: &mut DirCache,
+    config: &Config,
+) {
+    match config.stat_strategy {
+        StatStrategy::None => {}
+        StatStrategy::Lazy => {
+            item.data = Some(serde_json::json!({ "path": path }));
+        }
+        StatStrategy::Eager => attach_path_details(item, path, cache, config),
+    }
+}
+
+fn resolve_completion_item(state: &mut ServerState, mut item: CompletionItem) -> CompletionItem {
+    if item.documentation.is_some() {
+        return item;
+    }
+    let path = item
+        .data
+        .as_ref()
+        .and_then(|data| data.get("path"))
+        .and_then(|path| path.as_str())
+        .map(PathBuf::from);
+    if let Some(path) = path {
+        attach_path_details(&mut item, &path, &mut state.cache, &state.config);
+    }
+    item
+}
+
+fn attach_path_details(
+    item: &mut CompletionItem,
+    path: &std::path::Path,
+    cache: &mut DirCache,
+    config: &Config,
+) {
+    item.detail = Some(PathMetadata::read(path).summary());
+    item.documentation = Some(Documentation::MarkupContent(MarkupContent {
+        kind: MarkupKind::Markdown,
+        value: path_markdown(path, cache, config),
+    }));
 }
 
 fn is_completion_allowed(
     state: &ServerState,
     text: &str,
+    tokens: &[StringToken],
     has_prefix_fallback: bool,
     string_start_offset: usize,
+    signatures: &[PathFunctionRule],
 ) -> bool {
     match state.config.context_gating {
-        ContextGating::Strict => is_path_context(text, string_start_offset),
+        ContextGating::Strict => {
+            is_path_context(text, tokens, string_start_offset, &state.config, signatures)
+        }
         ContextGating::Off => true,
         ContextGating::Smart => {
             if has_prefix_fallback {
                 true
             } else {
-                is_path_context(text, string_start_offset)
+                is_path_context(text, tokens, string_start_offset, &state.config, signatures)
             }
         }
     }
 }
 
+/// Builds the item for one entry, escaping the inserted text for the literal
+/// it lands in. Names that a raw literal cannot express yield `None`.
 fn completion_item(
     name: String,
     is_dir: bool,
     range: Range,
     config: &Config,
     info: &completion::StringInfo,
-) -> CompletionItem {
+) -> Option<CompletionItem> {
     let mut insert_text = name.clone();
     if is_dir && config.directory_trailing_slash {
-        let sep = separator_for_insertion(&info.content_before_cursor, config);
+        let sep = match separator_for_insertion(&info.content_before_cursor, config) {
+            // A raw literal cannot end in a lone backslash.
+            '\\' if info.kind.is_raw => '/',
+            sep => sep,
+        };
         insert_text.push(sep);
     }
-    CompletionItem {
+    let insert_text = info.kind.escape(&insert_text)?;
+    // Editors filter against the source text in the range, which is escaped.
+    let filter_text = info.kind.escape(&name).filter(|escaped| *escaped != name);
+    Some(CompletionItem {
         label: name,
         kind: Some(if is_dir {
             CompletionItemKind::FOLDER
@@ -390,46 +1219,58 @@ fn completion_item(
             range,
             new_text: insert_text,
         })),
+        filter_text,
         ..CompletionItem::default()
-    }
+    })
 }
 
-fn list_dir_entries(
-    dir: &PathBuf,
-    cache: &mut DirCache,
-    config: &Config,
-) -> Option<Vec<(String, bool, PathBuf)>> {
-    if let Some(cached) = cache.get(dir) {
-        let entries = cached
-            .into_iter()
-            .map(|e| {
-                let name = e.name;
-                let path = dir.join(&name);
-                (name, e.is_dir, path)
-            })
-            .collect();
-        return Some(entries);
-    }
-
-    let mut items: Vec<DirEntryInfo> = Vec::new();
-    let mut entries: Vec<(String, bool, PathBuf)> = Vec::new();
-    let read_dir = std::fs::read_dir(dir).ok()?;
-    for entry in read_dir.take(config.max_results * 2) {
-        let Ok(entry) = entry else { continue };
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        let is_dir = match config.stat_strategy {
-            config::StatStrategy::None => false,
-            _ => entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
-        };
-        items.push(DirEntryInfo {
-            name: file_name.clone(),
-            is_dir,
-        });
-        entries.push((file_name, is_dir, dir.join(entry.file_name())));
+/// The directory relative paths in the document at `uri` resolve from. A
+/// notebook uses `notebook_working_dir` when set, standing in for the kernel's
+/// working directory, and otherwise its own directory.
+fn document_dir(state: &ServerState, uri: &Url) -> Option<PathBuf> {
+    let file_dir = base_dir_from_uri(uri, None);
+    let working_dir = state.config.notebook_working_dir.as_deref();
+    let Some(dir) = working_dir.filter(|_| state.notebooks.contains_key(uri)) else {
+        return file_dir;
+    };
+    if let Some(rest) = dir.strip_prefix('~') {
+        let rest = rest.trim_start_matches(['/', '\\']);
+        return dirs_home().map(|home| home.join(rest));
     }
+    let dir = PathBuf::from(dir);
+    if dir.is_absolute() {
+        return Some(dir);
+    }
+    workspace_root_dir(state)
+        .or(file_dir)
+        .map(|base| base.join(dir))
+}
+
+/// Points a request about a notebook cell at its notebook's combined source,
+/// returning the cell so that results can be mapped back to it.
+fn redirect_cell(state: &ServerState, uri: &mut Url) -> Option<CellSpan> {
+    let (notebook, cell) = state
+        .notebooks
+        .iter()
+        .find_map(|(notebook_uri, notebook)| Some((notebook_uri, notebook.cell(uri)?)))?;
+    *uri = notebook.clone();
+    Some(cell.clone())
+}
 
-    cache.insert(dir, items);
-    Some(entries)
+fn redirect_cell_position(
+    state: &ServerState,
+    params: &mut TextDocumentPositionParams,
+) -> Option<CellSpan> {
+    let cell = redirect_cell(state, &mut params.text_document.uri)?;
+    params.position = cell.to_notebook_position(params.position);
+    Some(cell)
+}
+
+fn workspace_root_dir(state: &ServerState) -> Option<PathBuf> {
+    state
+        .root_uri
+        .as_ref()
+        .and_then(|uri| uri.to_file_path().ok())
 }
 
 fn is_python_document(uri: &Url, language_id: Option<&str>) -> bool {
@@ -445,15 +1286,20 @@ fn get_line(text: &str, line: u32) -> Option<&str> {
     text.split('\n').nth(line as usize)
 }
 
+/// Converts an LSP position to a byte offset into `text`.
+fn document_offset(text: &str, position: Position) -> Option<usize> {
+    let line = get_line(text, position.line)?;
+    let line_start = line_start_offset(text, position.line)?;
+    Some(line_start + utf16_col_to_byte(line, position.character)?)
+}
+
 fn line_start_offset(text: &str, line: u32) -> Option<usize> {
     let mut offset = 0usize;
-    let mut current = 0u32;
-    for part in text.split('\n') {
-        if current == line {
+    for (current, part) in text.split('\n').enumerate() {
+        if current == line as usize {
             return Some(offset);
         }
         offset += part.len() + 1;
-        current += 1;
     }
     None
 }
@@ -485,6 +1331,280 @@ fn log_debug(state: &ServerState, message: &str) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testutil::TempDir;
+    use crate::tokenizer::LiteralKind;
+
+    fn test_state(uri: &Url, text: &str) -> ServerState {
+        let config = Config::default();
+        let mut documents = HashMap::new();
+        documents.insert(
+            uri.clone(),
+            DocumentState::new(text.into(), Some("python".into())),
+        );
+        ServerState {
+            documents,
+            notebooks: HashMap::new(),
+            root_uri: None,
+            cache: DirCache::new(Duration::from_millis(config.cache_ttl_ms), 8),
+            config,
+            config_warned: false,
+            debug: false,
+            pending_config_request: None,
+            pending_diagnostics: HashMap::new(),
+            next_request_id: 1,
+            index: None,
+            signatures: SignatureCache::default(),
+            ignore_cache: IgnoreCache::default(),
+            watch_files_supported: false,
+        }
+    }
+
+    fn position_params(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+            position: Position::new(line, character),
+        }
+    }
+
+    #[test]
+    fn definition_points_at_resolved_file() {
+        let root = TempDir::new("definition");
+        std::fs::write(root.join("train.yaml"), "").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "cfg = open(\"train.yaml\")\n");
+
+        let params = GotoDefinitionParams {
+            text_document_position_params: position_params(&uri, 0, 14),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let response = definition_locations(&mut state, params);
+        let outside = GotoDefinitionParams {
+            text_document_position_params: position_params(&uri, 0, 2),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        let missing = definition_locations(&mut state, outside);
+
+        let Some(GotoDefinitionResponse::Scalar(location)) = response else {
+            panic!("expected a single location");
+        };
+        assert!(location.uri.path().ends_with("/train.yaml"));
+        assert!(missing.is_none());
+    }
+
+    #[test]
+    fn hover_skips_plain_strings() {
+        let root = TempDir::new("hover-plain");
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let text = "print(\"hello\")\nprint(\"notes.txt\")\nopen(\"missing.txt\")\n";
+        let mut state = test_state(&uri, text);
+        let mut hover = |line: u32| {
+            let params = HoverParams {
+                text_document_position_params: position_params(&uri, line, 8),
+                work_done_progress_params: Default::default(),
+            };
+            hover_item(&mut state, params)
+        };
+
+        assert!(hover(0).is_none());
+        assert!(hover(1).is_some());
+        assert!(hover(2).is_some());
+    }
+
+    #[test]
+    fn resolve_fills_lazy_item_details() {
+        let root = TempDir::new("resolve");
+        std::fs::write(root.join("notes.txt"), "hello\n").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "");
+
+        let item = CompletionItem {
+            label: "notes.txt".into(),
+            data: Some(serde_json::json!({ "path": root.join("notes.txt") })),
+            ..CompletionItem::default()
+        };
+        let resolved = resolve_completion_item(&mut state, item);
+
+        assert!(resolved.detail.unwrap().starts_with("file · 6 B"));
+        let Some(Documentation::MarkupContent(doc)) = resolved.documentation else {
+            panic!("expected markdown documentation");
+        };
+        assert!(doc.value.contains("hello"));
+    }
+
+    fn completion_params(uri: &Url, line: u32, character: u32) -> CompletionParams {
+        CompletionParams {
+            text_document_position: position_params(uri, line, character),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: None,
+        }
+    }
+
+    #[test]
+    fn completion_filters_before_truncating() {
+        let root = TempDir::new("truncate");
+        for idx in 0..30 {
+            std::fs::write(root.join(format!("f{idx:02}.txt")), "").unwrap();
+        }
+        std::fs::write(root.join("zz_target.txt"), "").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "open(\"./zz\")\nopen(\"./\")\n");
+        state.config.max_results = 5;
+
+        let narrowed = completion_items(&mut state, completion_params(&uri, 0, 10));
+        let capped = completion_items(&mut state, completion_params(&uri, 1, 8));
+
+        assert!(!narrowed.is_incomplete);
+        assert_eq!(narrowed.items.len(), 1);
+        assert_eq!(narrowed.items[0].label, "zz_target.txt");
+        assert!(capped.is_incomplete);
+        assert_eq!(capped.items.len(), 5);
+    }
+
+    #[test]
+    fn fuzzy_items_keep_server_order() {
+        let root = TempDir::new("fuzzy");
+        std::fs::write(root.join("train_split.csv"), "").unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "open(\"./trcsv\")\n");
+        state.config.matching = MatchingStrategy::Fuzzy;
+
+        let list = completion_items(&mut state, completion_params(&uri, 0, 13));
+
+        assert!(list.is_incomplete);
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].label, "train_split.csv");
+        assert_eq!(list.items[0].filter_text.as_deref(), Some("trcsv"));
+        assert_eq!(list.items[0].sort_text.as_deref(), Some("00000"));
+    }
+
+    #[test]
+    fn bare_segment_falls_back_to_workspace_index() {
+        let root = TempDir::new("anywhere");
+        std::fs::create_dir_all(root.join("configs").join("experiments")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("configs/experiments/train.yaml"), "").unwrap();
+        let uri = Url::from_file_path(root.join("src").join("main.py")).unwrap();
+        let mut state = test_state(&uri, "open(\"train.ya\")\n");
+        state.root_uri = Url::from_directory_path(&root).ok();
+        state.config.workspace_index = true;
+        refresh_workspace_index(&mut state);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while state
+            .index
+            .as_ref()
+            .is_some_and(|index| !index.is_complete())
+            && Instant::now() < deadline
+        {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let from_file_dir = completion_items(&mut state, completion_params(&uri, 0, 14));
+        state.config.base_dir = BaseDirStrategy::WorkspaceRoot;
+        let from_root = completion_items(&mut state, completion_params(&uri, 0, 14));
+
+        assert_eq!(from_file_dir.items.len(), 1);
+        assert_eq!(
+            from_file_dir.items[0].label,
+            "../configs/experiments/train.yaml"
+        );
+        assert_eq!(from_root.items[0].label, "configs/experiments/train.yaml");
+        assert_eq!(from_root.items[0].filter_text.as_deref(), Some("train.ya"));
+    }
+
+    #[test]
+    fn joined_literal_lists_the_evaluated_base() {
+        let root = TempDir::new("joined");
+        std::fs::create_dir_all(root.join("src").join("data").join("raw")).unwrap();
+        std::fs::write(root.join("src/data/raw/train.csv"), "").unwrap();
+        let uri = Url::from_file_path(root.join("src").join("main.py")).unwrap();
+        let text = "DATA = Path(__file__).parent / \"data\"\nx = DATA / \"raw/t\"\n";
+        let mut state = test_state(&uri, text);
+        state.config.context_gating = ContextGating::Strict;
+        state.config.base_dir = BaseDirStrategy::WorkspaceRoot;
+        state.root_uri = Url::from_directory_path(&root).ok();
+        let list = completion_items(&mut state, completion_params(&uri, 1, 17));
+
+        let labels: Vec<&str> = list.items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["train.csv"]);
+        // Tokens and bindings are built on the stored document and kept.
+        let doc = &state.documents[&uri];
+        assert!(doc.strings.get().is_some() && doc.bindings.get().is_some());
+    }
+
+    #[test]
+    fn join_arguments_list_the_directory_built_so_far() {
+        let root = TempDir::new("join-args");
+        std::fs::create_dir_all(root.join("data").join("raw").join("v1")).unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let text = "p = os.path.join(\"data\", \"raw\", \"v\")\n";
+        let mut state = test_state(&uri, text);
+        state.config.context_gating = ContextGating::Strict;
+        let list = completion_items(&mut state, completion_params(&uri, 0, 34));
+
+        let labels: Vec<&str> = list.items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, vec!["v1"]);
+    }
+
+    #[test]
+    fn document_signatures_refresh_on_watched_file_changes() {
+        let root = TempDir::new("doc-signatures");
+        let module = root.join("helpers.py");
+        std::fs::write(&module, "def load(path: Path): ...\n").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "from helpers import load, save\n");
+        state.root_uri = Url::from_directory_path(&root).ok();
+        assert_eq!(document_signatures(&mut state, &uri).len(), 1);
+
+        std::fs::write(
+            &module,
+            "def load(path: Path): ...\ndef save(dst: Path): ...\n",
+        )
+        .unwrap();
+        assert_eq!(document_signatures(&mut state, &uri).len(), 1);
+        let change = FileEvent {
+            uri: Url::from_file_path(&module).unwrap(),
+            typ: FileChangeType::CHANGED,
+        };
+        apply_file_changes(&mut state, vec![change]);
+
+        let rules = document_signatures(&mut state, &uri);
+        let calls: Vec<&str> = rules.iter().map(|rule| rule.call.as_str()).collect();
+        assert_eq!(calls, vec!["load", "save"]);
+    }
+
+    #[test]
+    fn code_actions_reuse_published_missing_paths() {
+        let root = TempDir::new("action-reuse");
+        std::fs::create_dir_all(root.join("datasets")).unwrap();
+        std::fs::write(root.join("datasets").join("train.csv"), "").unwrap();
+        let uri = Url::from_file_path(root.join("main.py")).unwrap();
+        let mut state = test_state(&uri, "open(\"datasets/trian.csv\")\n");
+        assert_eq!(document_diagnostics(&mut state, &uri).len(), 1);
+        assert_eq!(
+            state.documents[&uri].missing.as_ref().map(Vec::len),
+            Some(1)
+        );
+
+        let params = CodeActionParams {
+            text_document: lsp_types::TextDocumentIdentifier { uri: uri.clone() },
+            range: Range::new(Position::new(0, 8), Position::new(0, 8)),
+            context: Default::default(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        };
+        assert!(!code_action_items(&mut state, params).is_empty());
+        state
+            .documents
+            .get_mut(&uri)
+            .unwrap()
+            .set_text("open(\"datasets/train.csv\")\n".into());
+        assert!(state.documents[&uri].missing.is_none());
+    }
 
     #[test]
     fn line_start_offset_basic() {
@@ -494,16 +1614,131 @@ mod tests {
     }
 
     #[test]
-    fn replacement_range_uses_segment_start() {
-        let info = completion::StringInfo {
-            content_before_cursor: "./foo/bar".into(),
-            is_raw: false,
-            is_fstring: false,
+    fn completion_text_is_escaped_for_the_literal() {
+        let mut info = completion::StringInfo {
+            content_before_cursor: "./foo/".into(),
+            kind: LiteralKind::default(),
             string_start_byte: 6,
-            string_start_utf16: 6,
+            segment_start_utf16: 12,
+        };
+        let config = Config {
+            prefer_forward_slashes: true,
+            ..Config::default()
         };
-        let segment_start = segment_start_offset(&info.content_before_cursor);
-        let utf16 = utf16_len(&info.content_before_cursor[..segment_start]);
-        assert_eq!(utf16, "./foo/".len() as u32);
+        let range = Range::default();
+        let item = completion_item("it\"s {x}".into(), true, range, &config, &info).unwrap();
+        let Some(lsp_types::CompletionTextEdit::Edit(edit)) = item.text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "it\\\"s {x}/");
+        assert_eq!(item.filter_text.as_deref(), Some("it\\\"s {x}"));
+
+        info.kind.is_fstring = true;
+        info.kind.quote = '\'';
+        let item = completion_item("it's {x}".into(), false, range, &config, &info).unwrap();
+        let Some(lsp_types::CompletionTextEdit::Edit(edit)) = item.text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "it\\'s {{x}}");
+
+        info.kind.is_raw = true;
+        assert!(completion_item("it's".into(), false, range, &config, &info).is_none());
+
+        info.content_before_cursor = r"C:\data\".into();
+        let config = Config {
+            prefer_forward_slashes: false,
+            ..Config::default()
+        };
+        let item = completion_item("dir".into(), true, range, &config, &info).unwrap();
+        let Some(lsp_types::CompletionTextEdit::Edit(edit)) = item.text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.new_text, "dir/");
+    }
+
+    #[test]
+    fn notebooks_have_no_file_binding() {
+        let uri = Url::from_file_path(std::env::temp_dir().join("nb.ipynb")).unwrap();
+        let text = "HERE = Path(__file__).parent\nx = HERE / \"data\"\n";
+        let offset = text.rfind('"').unwrap() - "data".len() - 1;
+        let script = DocumentState::new(text.into(), Some("python".into()));
+        let notebook = DocumentState::notebook(text.into());
+
+        let base = |doc: &DocumentState| {
+            literal_base(&doc.text, doc.strings(), offset, doc.bindings(&uri))
+        };
+        assert!(base(&script).is_some());
+        assert!(base(&notebook).is_none());
+    }
+
+    #[test]
+    fn notebook_cells_complete_against_the_working_dir() {
+        let root = TempDir::new("notebook");
+        std::fs::create_dir_all(root.join("notebooks/data")).unwrap();
+        std::fs::create_dir_all(root.join("runs")).unwrap();
+        std::fs::write(root.join("notebooks/data/train.csv"), "").unwrap();
+        std::fs::write(root.join("runs/metrics.csv"), "").unwrap();
+        let notebook = Url::from_file_path(root.join("notebooks/nb.ipynb")).unwrap();
+        let cell =
+            |idx: usize| Url::parse(&format!("vscode-notebook-cell:/nb.ipynb#c{idx}")).unwrap();
+        let (server, client) = Connection::memory();
+        let mut state = test_state(&notebook, "");
+        state.documents.clear();
+        state.root_uri = Some(Url::from_file_path(&root).unwrap());
+
+        let open = serde_json::json!({
+            "notebookDocument": {
+                "uri": notebook,
+                "notebookType": "jupyter-notebook",
+                "version": 0,
+                "cells": [
+                    { "kind": 2, "document": cell(0) },
+                    { "kind": 1, "document": cell(1) },
+                    { "kind": 2, "document": cell(2) },
+                ],
+            },
+            "cellTextDocuments": [
+                { "uri": cell(0), "languageId": "python", "version": 0,
+                  "text": "from pathlib import Path\nDATA = Path(\"data\")" },
+                { "uri": cell(1), "languageId": "markdown", "version": 0, "text": "# Load" },
+                { "uri": cell(2), "languageId": "python", "version": 0,
+                  "text": "import pandas as pd\ndf = pd.read_csv(DATA / \"t\")" },
+            ],
+        });
+        let notification = Notification::new("notebookDocument/didOpen".into(), open);
+        handle_notification(&server, &mut state, &notification);
+
+        let complete = |state: &mut ServerState, line: u32, character: u32| {
+            let params = completion_params(&cell(2), line, character);
+            let request =
+                Request::new(RequestId::from(1), "textDocument/completion".into(), params);
+            handle_request(&server, state, &request);
+            let Ok(Message::Response(response)) = client.receiver.try_recv() else {
+                panic!("expected a response");
+            };
+            serde_json::from_value::<CompletionList>(response.result.unwrap()).unwrap()
+        };
+        let list = complete(&mut state, 1, 26);
+        assert_eq!(list.items.len(), 1);
+        assert_eq!(list.items[0].label, "train.csv");
+        let Some(lsp_types::CompletionTextEdit::Edit(edit)) = &list.items[0].text_edit else {
+            panic!("expected a text edit");
+        };
+        assert_eq!(edit.range.start, Position::new(1, 25));
+
+        let change = serde_json::json!({
+            "notebookDocument": { "uri": notebook, "version": 1 },
+            "change": { "cells": { "textContent": [{
+                "document": { "uri": cell(2), "version": 1 },
+                "changes": [{ "text": "import pandas as pd\ndf = pd.read_csv(\"\")" }],
+            }] } },
+        });
+        let notification = Notification::new("notebookDocument/didChange".into(), change);
+        handle_notification(&server, &mut state, &notification);
+        state.config.notebook_working_dir = Some("runs".into());
+        let list = complete(&mut state, 1, 18);
+
+        let labels: Vec<&str> = list.items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["metrics.csv"]);
     }
 }
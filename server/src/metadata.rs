@@ -0,0 +1,257 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const PREVIEW_MAX_BYTES: usize = 8 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathKind {
+    File,
+    Directory,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub struct PathMetadata {
+    pub exists: bool,
+    pub kind: PathKind,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+    pub permissions: Option<String>,
+    pub symlink_target: Option<PathBuf>,
+}
+
+impl PathMetadata {
+    pub fn read(path: &Path) -> Self {
+        let symlink_target = fs::symlink_metadata(path)
+            .ok()
+            .filter(|meta| meta.file_type().is_symlink())
+            .and_then(|_| fs::read_link(path).ok());
+        let Ok(meta) = fs::metadata(path) else {
+            return Self {
+                exists: false,
+                kind: PathKind::Other,
+                size: 0,
+                modified: None,
+                permissions: None,
+                symlink_target,
+            };
+        };
+        let kind = if meta.is_dir() {
+            PathKind::Directory
+        } else if meta.is_file() {
+            PathKind::File
+        } else {
+            PathKind::Other
+        };
+        Self {
+            exists: true,
+            kind,
+            size: meta.len(),
+            modified: meta.modified().ok(),
+            permissions: Some(format_permissions(&meta)),
+            symlink_target,
+        }
+    }
+
+    /// One-line summary, e.g. `file · 1.2 KiB · 2024-05-01 12:00 UTC`.
+    pub fn summary(&self) -> String {
+        if !self.exists {
+            return "missing".into();
+        }
+        let mut parts = vec![self.kind_label().to_string()];
+        if self.kind == PathKind::File {
+            parts.push(format_size(self.size));
+        }
+        if let Some(modified) = self.modified {
+            parts.push(format_timestamp(modified));
+        }
+        if let Some(target) = &self.symlink_target {
+            parts.push(format!("→ {}", target.display()));
+        }
+        parts.join(" · ")
+    }
+
+    /// Markdown bullet list with every known fact about the path.
+    pub fn markdown_facts(&self) -> String {
+        let mut lines = Vec::new();
+        if !self.exists {
+            lines.push("- exists: no".to_string());
+        } else {
+            lines.push("- exists: yes".to_string());
+            lines.push(format!("- type: {}", self.kind_label()));
+            if self.kind == PathKind::File {
+                lines.push(format!(
+                    "- size: {} ({} bytes)",
+                    format_size(self.size),
+                    self.size
+                ));
+            }
+            if let Some(modified) = self.modified {
+                lines.push(format!("- modified: {}", format_timestamp(modified)));
+            }
+            if let Some(permissions) = &self.permissions {
+                lines.push(format!("- permissions: `{permissions}`"));
+            }
+        }
+        if let Some(target) = &self.symlink_target {
+            lines.push(format!("- symlink → `{}`", target.display()));
+        }
+        lines.join("\n")
+    }
+
+    fn kind_label(&self) -> &'static str {
+        match self.kind {
+            PathKind::File => "file",
+            PathKind::Directory => "directory",
+            PathKind::Other => "special file",
+        }
+    }
+}
+
+/// Returns the first `max_lines` lines of a text file, or `None` when the
+/// file cannot be read or looks binary.
+pub fn text_preview(path: &Path, max_lines: usize) -> Option<String> {
+    if max_lines == 0 {
+        return None;
+    }
+    let mut file = fs::File::open(path).ok()?;
+    let mut buf = vec![0u8; PREVIEW_MAX_BYTES];
+    let mut len = 0usize;
+    while len < buf.len() {
+        match file.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(_) => return None,
+        }
+    }
+    buf.truncate(len);
+    if buf.contains(&0) {
+        return None;
+    }
+    let text = match std::str::from_utf8(&buf) {
+        Ok(text) => text,
+        // A multi-byte character may have been cut at the read limit.
+        Err(err) if err.error_len().is_none() => {
+            std::str::from_utf8(&buf[..err.valid_up_to()]).ok()?
+        }
+        Err(_) => return None,
+    };
+    let preview: Vec<&str> = text.lines().take(max_lines).collect();
+    Some(preview.join("\n"))
+}
+
+/// Wraps `text` in a fenced code block that cannot be closed by its contents.
+pub fn fenced(text: &str, lang: &str) -> String {
+    let mut fence = "```".to_string();
+    while text.contains(fence.as_str()) {
+        fence.push('`');
+    }
+    format!("{fence}{lang}\n{text}\n{fence}")
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = 0usize;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+pub fn format_timestamp(time: SystemTime) -> String {
+    let Ok(since_epoch) = time.duration_since(UNIX_EPOCH) else {
+        return "before 1970".into();
+    };
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let rem = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        rem / 3600,
+        (rem % 3600) / 60
+    )
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(unix)]
+fn format_permissions(meta: &fs::Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = meta.permissions().mode();
+    let flags = ['r', 'w', 'x'];
+    (0..9)
+        .map(|bit| {
+            if mode & (1 << (8 - bit)) != 0 {
+                flags[bit % 3]
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+fn format_permissions(meta: &fs::Metadata) -> String {
+    if meta.permissions().readonly() {
+        "read-only".into()
+    } else {
+        "read-write".into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+    }
+
+    #[test]
+    fn formats_timestamps_in_utc() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(format_timestamp(time), "2023-11-14 22:13 UTC");
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01 00:00 UTC");
+    }
+
+    #[test]
+    fn previews_text_and_skips_binary() {
+        let dir = TempDir::new("meta");
+        std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(dir.join("b.bin"), [0u8, 1, 2]).unwrap();
+
+        let text = text_preview(&dir.join("a.txt"), 2);
+        let binary = text_preview(&dir.join("b.bin"), 2);
+        let meta = PathMetadata::read(&dir.join("a.txt"));
+
+        assert_eq!(text.as_deref(), Some("one\ntwo"));
+        assert!(binary.is_none());
+        assert!(meta.exists);
+        assert_eq!(meta.kind, PathKind::File);
+        assert_eq!(meta.size, 14);
+    }
+}
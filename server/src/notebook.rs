@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+
+use lsp_types::{
+    CodeActionOrCommand, CompletionList, CompletionTextEdit, Diagnostic, DocumentLink, Position,
+    Range, TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem, Url,
+    VersionedTextDocumentIdentifier,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+// LSP 3.17 notebook synchronization. lsp-types 0.95 has no notebook types, so
+// the parts of the protocol the server reads are declared here.
+
+/// Markup (1) or code (2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct NotebookCellKind(i32);
+
+impl NotebookCellKind {
+    pub const CODE: Self = Self(2);
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NotebookCell {
+    pub kind: NotebookCellKind,
+    /// The URI of the cell's text document.
+    pub document: Url,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookDocument {
+    pub uri: Url,
+    pub cells: Vec<NotebookCell>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotebookDocumentIdentifier {
+    pub uri: Url,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidOpenNotebookDocumentParams {
+    pub notebook_document: NotebookDocument,
+    pub cell_text_documents: Vec<TextDocumentItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeNotebookDocumentParams {
+    pub notebook_document: NotebookDocumentIdentifier,
+    pub change: NotebookDocumentChangeEvent,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotebookDocumentChangeEvent {
+    pub cells: Option<NotebookCellsChange>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellsChange {
+    pub structure: Option<NotebookCellsStructureChange>,
+    /// Cells whose kind or metadata changed.
+    pub data: Option<Vec<NotebookCell>>,
+    pub text_content: Option<Vec<NotebookCellTextChange>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellsStructureChange {
+    pub array: NotebookCellArrayChange,
+    pub did_open: Option<Vec<TextDocumentItem>>,
+    pub did_close: Option<Vec<TextDocumentIdentifier>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotebookCellArrayChange {
+    pub start: usize,
+    pub delete_count: usize,
+    pub cells: Option<Vec<NotebookCell>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NotebookCellTextChange {
+    pub document: VersionedTextDocumentIdentifier,
+    pub changes: Vec<TextDocumentContentChangeEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidCloseNotebookDocumentParams {
+    pub notebook_document: NotebookDocumentIdentifier,
+    pub cell_text_documents: Vec<TextDocumentIdentifier>,
+}
+
+/// The `notebookDocumentSync` server capability: Python cells of any notebook.
+pub fn sync_options() -> Value {
+    serde_json::json!({
+        "notebookSelector": [{ "notebook": "*", "cells": [{ "language": "python" }] }],
+    })
+}
+
+#[derive(Debug, Clone)]
+struct CellText {
+    text: String,
+    language_id: String,
+}
+
+/// An open notebook: its cells in order and the text of each cell document.
+#[derive(Debug, Default)]
+pub struct Notebook {
+    cells: Vec<NotebookCell>,
+    texts: HashMap<Url, CellText>,
+    /// Where each Python code cell sits in `source`, rebuilt on every change.
+    spans: Vec<CellSpan>,
+}
+
+/// A cell's place in its notebook's combined source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellSpan {
+    pub uri: Url,
+    pub first_line: u32,
+    pub line_count: u32,
+}
+
+impl Notebook {
+    pub fn open(params: DidOpenNotebookDocumentParams) -> Self {
+        let mut notebook = Self {
+            cells: params.notebook_document.cells,
+            ..Self::default()
+        };
+        notebook.open_cells(params.cell_text_documents);
+        notebook
+    }
+
+    fn open_cells(&mut self, documents: Vec<TextDocumentItem>) {
+        for document in documents {
+            let text = CellText {
+                text: document.text,
+                language_id: document.language_id,
+            };
+            self.texts.insert(document.uri, text);
+        }
+    }
+
+    /// Applies a `notebookDocument/didChange` event and returns the cell
+    /// documents it closed.
+    pub fn apply(&mut self, change: NotebookDocumentChangeEvent) -> Vec<Url> {
+        let mut closed = Vec::new();
+        let Some(cells) = change.cells else {
+            return closed;
+        };
+        if let Some(structure) = cells.structure {
+            let start = structure.array.start.min(self.cells.len());
+            let end = (start + structure.array.delete_count).min(self.cells.len());
+            self.cells
+                .splice(start..end, structure.array.cells.unwrap_or_default());
+            self.open_cells(structure.did_open.unwrap_or_default());
+            for document in structure.did_close.unwrap_or_default() {
+                self.texts.remove(&document.uri);
+                closed.push(document.uri);
+            }
+        }
+        for data in cells.data.unwrap_or_default() {
+            if let Some(cell) = self.cells.iter_mut().find(|c| c.document == data.document) {
+                *cell = data;
+            }
+        }
+        for content in cells.text_content.unwrap_or_default() {
+            let Some(cell) = self.texts.get_mut(&content.document.uri) else {
+                continue;
+            };
+            if let Some(change) = content.changes.last() {
+                cell.text = change.text.clone();
+            }
+        }
+        closed
+    }
+
+    /// Joins the Python code cells into one source, in notebook order, so that
+    /// names and imports from earlier cells carry over to later ones.
+    pub fn source(&mut self) -> String {
+        let mut source = String::new();
+        let mut line = 0u32;
+        self.spans.clear();
+        for cell in &self.cells {
+            let Some(text) = self.texts.get(&cell.document) else {
+                continue;
+            };
+            if cell.kind != NotebookCellKind::CODE
+                || !text.language_id.eq_ignore_ascii_case("python")
+            {
+                continue;
+            }
+            if !self.spans.is_empty() {
+                source.push('\n');
+            }
+            source.push_str(&text.text);
+            let line_count = text.text.matches('\n').count() as u32 + 1;
+            self.spans.push(CellSpan {
+                uri: cell.document.clone(),
+                first_line: line,
+                line_count,
+            });
+            line += line_count;
+        }
+        source
+    }
+
+    pub fn cell(&self, uri: &Url) -> Option<&CellSpan> {
+        self.spans.iter().find(|span| span.uri == *uri)
+    }
+
+    /// Splits diagnostics for the combined source among the cells. Cells left
+    /// out of it get an empty list, clearing any they had.
+    pub fn cell_diagnostics(&self, diagnostics: &[Diagnostic]) -> Vec<(Url, Vec<Diagnostic>)> {
+        self.cells
+            .iter()
+            .map(|cell| {
+                let diagnostics = self
+                    .cell(&cell.document)
+                    .map(|span| span.diagnostics_to_cell(diagnostics))
+                    .unwrap_or_default();
+                (cell.document.clone(), diagnostics)
+            })
+            .collect()
+    }
+}
+
+impl CellSpan {
+    pub fn to_notebook_position(&self, position: Position) -> Position {
+        Position {
+            line: position.line + self.first_line,
+            ..position
+        }
+    }
+
+    pub fn to_notebook_range(&self, range: Range) -> Range {
+        Range {
+            start: self.to_notebook_position(range.start),
+            end: self.to_notebook_position(range.end),
+        }
+    }
+
+    /// The cell-relative form of a range in the combined source, or `None`
+    /// when it does not lie within this cell.
+    pub fn to_cell_range(&self, range: Range) -> Option<Range> {
+        let lines = self.first_line..self.first_line + self.line_count;
+        if !lines.contains(&range.start.line) || !lines.contains(&range.end.line) {
+            return None;
+        }
+        let shift = |position: Position| Position {
+            line: position.line - self.first_line,
+            ..position
+        };
+        Some(Range {
+            start: shift(range.start),
+            end: shift(range.end),
+        })
+    }
+
+    pub fn completions_to_cell(&self, list: &mut CompletionList) {
+        list.items.retain_mut(|item| match &mut item.text_edit {
+            Some(CompletionTextEdit::Edit(edit)) => match self.to_cell_range(edit.range) {
+                Some(range) => {
+                    edit.range = range;
+                    true
+                }
+                None => false,
+            },
+            _ => true,
+        });
+    }
+
+    pub fn diagnostics_to_cell(&self, diagnostics: &[Diagnostic]) -> Vec<Diagnostic> {
+        diagnostics
+            .iter()
+            .filter_map(|diagnostic| {
+                let range = self.to_cell_range(diagnostic.range)?;
+                Some(Diagnostic {
+                    range,
+                    ..diagnostic.clone()
+                })
+            })
+            .collect()
+    }
+
+    pub fn links_to_cell(&self, links: Vec<DocumentLink>) -> Vec<DocumentLink> {
+        links
+            .into_iter()
+            .filter_map(|link| {
+                let range = self.to_cell_range(link.range)?;
+                Some(DocumentLink { range, ..link })
+            })
+            .collect()
+    }
+
+    /// Points the edits and diagnostics of actions computed for `notebook`'s
+    /// combined source at this cell.
+    pub fn actions_to_cell(&self, notebook: &Url, actions: &mut [CodeActionOrCommand]) {
+        for action in actions {
+            let CodeActionOrCommand::CodeAction(action) = action else {
+                continue;
+            };
+            if let Some(diagnostics) = &mut action.diagnostics {
+                *diagnostics = self.diagnostics_to_cell(diagnostics);
+            }
+            let Some(changes) = action.edit.as_mut().and_then(|edit| edit.changes.as_mut()) else {
+                continue;
+            };
+            if let Some(mut edits) = changes.remove(notebook) {
+                for edit in &mut edits {
+                    edit.range = self.to_cell_range(edit.range).unwrap_or(edit.range);
+                }
+                changes.insert(self.uri.clone(), edits);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell_uri(idx: usize) -> Url {
+        Url::parse(&format!("vscode-notebook-cell:/work/nb.ipynb#cell{idx}")).unwrap()
+    }
+
+    fn open(cells: &[(i32, &str, &str)]) -> Notebook {
+        let params = serde_json::json!({
+            "notebookDocument": {
+                "uri": "file:///work/nb.ipynb",
+                "notebookType": "jupyter-notebook",
+                "version": 0,
+                "cells": cells.iter().enumerate().map(|(idx, (kind, _, _))| {
+                    serde_json::json!({ "kind": kind, "document": cell_uri(idx) })
+                }).collect::<Vec<_>>(),
+            },
+            "cellTextDocuments": cells.iter().enumerate().map(|(idx, (_, language, text))| {
+                serde_json::json!({
+                    "uri": cell_uri(idx),
+                    "languageId": language,
+                    "version": 0,
+                    "text": text,
+                })
+            }).collect::<Vec<_>>(),
+        });
+        Notebook::open(serde_json::from_value(params).unwrap())
+    }
+
+    #[test]
+    fn joins_python_code_cells_and_maps_positions() {
+        let mut notebook = open(&[
+            (2, "python", "import pandas as pd\nDATA = \"data\"\n"),
+            (1, "markdown", "# Notes"),
+            (2, "python", "pd.read_csv(\"data/\")"),
+        ]);
+        let source = notebook.source();
+        assert_eq!(
+            source,
+            "import pandas as pd\nDATA = \"data\"\n\npd.read_csv(\"data/\")"
+        );
+        let cell = notebook.cell(&cell_uri(2)).unwrap().clone();
+        assert_eq!(cell.first_line, 3);
+        assert!(notebook.cell(&cell_uri(1)).is_none());
+
+        let position = cell.to_notebook_position(Position::new(0, 13));
+        assert_eq!(position, Position::new(3, 13));
+        let range = Range::new(position, Position::new(3, 18));
+        assert_eq!(
+            cell.to_cell_range(range),
+            Some(Range::new(Position::new(0, 13), Position::new(0, 18)))
+        );
+        let first = notebook.cell(&cell_uri(0)).unwrap();
+        assert_eq!(first.to_cell_range(range), None);
+    }
+
+    #[test]
+    fn applies_structure_and_text_changes() {
+        let mut notebook = open(&[(2, "python", "a = 1"), (2, "python", "b = 2")]);
+        let change = serde_json::json!({
+            "cells": {
+                "structure": {
+                    "array": {
+                        "start": 0,
+                        "deleteCount": 1,
+                        "cells": [{ "kind": 2, "document": cell_uri(5) }],
+                    },
+                    "didOpen": [{
+                        "uri": cell_uri(5),
+                        "languageId": "python",
+                        "version": 0,
+                        "text": "c = 3",
+                    }],
+                    "didClose": [{ "uri": cell_uri(0) }],
+                },
+                "textContent": [{
+                    "document": { "uri": cell_uri(1), "version": 1 },
+                    "changes": [{ "text": "b = 4" }],
+                }],
+            },
+        });
+        let closed = notebook.apply(serde_json::from_value(change).unwrap());
+        assert_eq!(closed, vec![cell_uri(0)]);
+        assert_eq!(notebook.source(), "c = 3\nb = 4");
+    }
+}
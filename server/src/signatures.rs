@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::config::{ArgSlot, ExpectedKind, PathFunctionRule};
+use crate::context::is_path_annotation;
+use crate::tokenizer::{token_at, tokenize_strings, StringToken};
+
+/// A module-level function with the arguments annotated as paths, as in
+/// `def load(src: Path | str, *, cache_dir: os.PathLike = ...)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    pub name: String,
+    pub args: Vec<ArgSlot>,
+}
+
+#[derive(Debug)]
+struct ScannedFile {
+    modified: Option<SystemTime>,
+    signatures: Vec<Signature>,
+}
+
+/// Signatures of the workspace modules documents import. A module is scanned
+/// when first imported and rescanned once its modification time changes or
+/// a watched-file event invalidates it.
+#[derive(Debug, Default)]
+pub struct SignatureCache {
+    files: HashMap<PathBuf, ScannedFile>,
+}
+
+impl SignatureCache {
+    pub fn invalidate(&mut self, path: &Path) {
+        self.files.remove(path);
+    }
+
+    /// Rules for calls in a document that reach functions with path
+    /// parameters: its own functions by name, imported ones by the name the
+    /// document imports them as (`load` after `from pkg.io import load`,
+    /// `pio.load` after `import pkg.io as pio`). Rule calls are exact.
+    pub fn rules_for(
+        &mut self,
+        text: &str,
+        tokens: &[StringToken],
+        file: Option<&Path>,
+        root: Option<&Path>,
+    ) -> Vec<PathFunctionRule> {
+        let mut rules: Vec<PathFunctionRule> = scan_signatures(text, tokens)
+            .iter()
+            .map(|signature| rule(signature.name.clone(), signature))
+            .collect();
+        let file_dir = file.and_then(Path::parent);
+        for import in scan_imports(text, tokens) {
+            if let Some((module, name)) = split_module(&import.qualified) {
+                for path in module_files(module, file_dir, root) {
+                    let found = self.signatures(&path).iter().find(|s| s.name == name);
+                    rules.extend(found.map(|signature| rule(import.local.clone(), signature)));
+                }
+            }
+            for path in module_files(&import.qualified, file_dir, root) {
+                for signature in self.signatures(&path) {
+                    let call = format!("{}.{}", import.local, signature.name);
+                    rules.push(rule(call, signature));
+                }
+            }
+        }
+        rules
+    }
+
+    fn signatures(&mut self, path: &Path) -> &[Signature] {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let fresh = self
+            .files
+            .get(path)
+            .is_some_and(|scanned| scanned.modified == modified);
+        if !fresh {
+            let text = std::fs::read_to_string(path).unwrap_or_default();
+            let tokens = tokenize_strings(&text);
+            let scanned = ScannedFile {
+                modified,
+                signatures: scan_signatures(&text, &tokens),
+            };
+            self.files.insert(path.to_path_buf(), scanned);
+        }
+        &self.files[path].signatures
+    }
+}
+
+fn rule(call: String, signature: &Signature) -> PathFunctionRule {
+    PathFunctionRule {
+        call,
+        args: signature.args.clone(),
+        kind: ExpectedKind::Any,
+        extensions: Vec::new(),
+    }
+}
+
+/// Finds the module-level `def`s of a module with parameters annotated as
+/// paths.
+pub fn scan_signatures(text: &str, tokens: &[StringToken]) -> Vec<Signature> {
+    let mut signatures = Vec::new();
+    for line_start in code_line_starts(text, tokens) {
+        let line = &text[line_start..];
+        let Some(rest) = line
+            .strip_prefix("async ")
+            .map(str::trim_start)
+            .unwrap_or(line)
+            .strip_prefix("def ")
+        else {
+            continue;
+        };
+        let rest = rest.trim_start();
+        let name_len = rest
+            .find(|ch: char| !(ch.is_alphanumeric() || ch == '_'))
+            .unwrap_or(rest.len());
+        let after_name = &rest[name_len..];
+        if name_len == 0 || !after_name.trim_start().starts_with('(') {
+            continue;
+        }
+        let open = text.len() - after_name.trim_start().len();
+        let Some(params) = parameters(text, tokens, open) else {
+            continue;
+        };
+        let args = path_slots(&params);
+        if !args.is_empty() {
+            signatures.push(Signature {
+                name: rest[..name_len].to_string(),
+                args,
+            });
+        }
+    }
+    signatures
+}
+
+/// Offsets of lines that start outside strings, like the column-0 statements
+/// of a module.
+fn code_line_starts<'a>(
+    text: &'a str,
+    tokens: &'a [StringToken],
+) -> impl Iterator<Item = usize> + 'a {
+    std::iter::once(0)
+        .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+        .filter(move |start| token_at(tokens, *start).is_none())
+}
+
+/// Splits the parameter list opening at `open` into parameters, dropping
+/// comments. Strings and nested brackets are kept whole.
+fn parameters(text: &str, tokens: &[StringToken], open: usize) -> Option<Vec<String>> {
+    let mut params = vec![String::new()];
+    let mut depth = 0usize;
+    let mut next_token = tokens.partition_point(|token| token.start <= open);
+    let mut i = open + 1;
+    while i < text.len() {
+        if let Some(token) = tokens.get(next_token).filter(|token| token.start == i) {
+            params.last_mut()?.push_str(&text[i..token.end()]);
+            i = token.end();
+            next_token += 1;
+            continue;
+        }
+        let ch = text[i..].chars().next()?;
+        match ch {
+            '#' => {
+                i += text[i..].find('\n').unwrap_or(text.len() - i);
+                continue;
+            }
+            '(' | '[' | '{' => depth += 1,
+            ')' if depth == 0 => return Some(params),
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                params.push(String::new());
+                i += 1;
+                continue;
+            }
+            _ => {}
+        }
+        params.last_mut()?.push(ch);
+        i += ch.len_utf8();
+    }
+    None
+}
+
+/// The argument slots of parameters annotated as paths. Positional-only
+/// parameters (before `/`) take no keyword; keyword-only ones (after `*`)
+/// take no position.
+fn path_slots(params: &[String]) -> Vec<ArgSlot> {
+    let mut slots = Vec::new();
+    let mut position = Some(0usize);
+    for param in params.iter().map(|param| param.trim()) {
+        if param.is_empty() {
+            continue;
+        }
+        if param == "/" {
+            slots.retain(|slot| matches!(slot, ArgSlot::Position(_)));
+            continue;
+        }
+        if param.starts_with('*') {
+            position = None;
+            continue;
+        }
+        let head = param.split_once('=').map_or(param, |(head, _)| head);
+        if let Some((name, annotation)) = head.split_once(':') {
+            if is_path_annotation(annotation) {
+                slots.extend(position.map(ArgSlot::Position));
+                slots.push(ArgSlot::Keyword(name.trim().to_string()));
+            }
+        }
+        if let Some(index) = position.as_mut() {
+            *index += 1;
+        }
+    }
+    slots
+}
+
+/// A name bound by an import statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Import {
+    local: String,
+    qualified: String,
+}
+
+/// Reads the `import` and `from ... import` statements of a document.
+fn scan_imports(text: &str, tokens: &[StringToken]) -> Vec<Import> {
+    let mut imports = Vec::new();
+    for line_start in code_line_starts(text, tokens) {
+        let statement = import_statement(&text[line_start..]);
+        let statement = statement.trim();
+        if let Some(names) = statement.strip_prefix("import ") {
+            for (name, alias) in import_names(names) {
+                imports.push(Import {
+                    local: alias.unwrap_or(name).to_string(),
+                    qualified: name.to_string(),
+                });
+            }
+        } else if let Some(rest) = statement.strip_prefix("from ") {
+            let Some((module, names)) = rest.split_once(" import ") else {
+                continue;
+            };
+            let module = module.trim();
+            for (name, alias) in import_names(names) {
+                let qualified = if module.ends_with('.') {
+                    format!("{module}{name}")
+                } else {
+                    format!("{module}.{name}")
+                };
+                imports.push(Import {
+                    local: alias.unwrap_or(name).to_string(),
+                    qualified,
+                });
+            }
+        }
+    }
+    imports
+}
+
+/// The text of the statement starting `text`, joined across bracketed and
+/// backslash-continued lines, without comments.
+fn import_statement(text: &str) -> String {
+    let mut statement = String::new();
+    let mut depth = 0usize;
+    for line in text.lines() {
+        let code = line.split('#').next().unwrap_or("");
+        depth += code.matches('(').count();
+        depth = depth.saturating_sub(code.matches(')').count());
+        let (code, continued) = match code.trim_end().strip_suffix('\\') {
+            Some(code) => (code, true),
+            None => (code, false),
+        };
+        statement.push_str(code);
+        statement.push(' ');
+        if depth == 0 && !continued {
+            break;
+        }
+    }
+    statement
+}
+
+/// Splits `a.b as c, d` or `(x as y, z)` into names and aliases. Star
+/// imports are skipped.
+fn import_names(names: &str) -> Vec<(&str, Option<&str>)> {
+    names
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .filter_map(|part| {
+            let mut words = part.split_whitespace();
+            let name = words.next().filter(|name| *name != "*")?;
+            let alias = match (words.next(), words.next()) {
+                (Some("as"), Some(alias)) => Some(alias),
+                _ => None,
+            };
+            Some((name, alias))
+        })
+        .collect()
+}
+
+/// Splits a qualified name into its module and last name, keeping the dots
+/// of relative modules: `.io.load` gives `.io` and `load`, `.io` gives `.`
+/// and `io`.
+fn split_module(qualified: &str) -> Option<(&str, &str)> {
+    let idx = qualified.rfind('.')?;
+    let module = &qualified[..idx];
+    if module.trim_start_matches('.').is_empty() {
+        return Some((&qualified[..=idx], &qualified[idx + 1..]));
+    }
+    Some((module, &qualified[idx + 1..]))
+}
+
+/// Source files a module may live in. Relative modules start from the
+/// importing file's directory; absolute ones are looked up next to it, at
+/// the workspace root and under `src/`.
+fn module_files(module: &str, file_dir: Option<&Path>, root: Option<&Path>) -> Vec<PathBuf> {
+    let rest = module.trim_start_matches('.');
+    let dots = module.len() - rest.len();
+    let bases: Vec<PathBuf> = if dots > 0 {
+        let mut base = file_dir.map(Path::to_path_buf);
+        for _ in 1..dots {
+            base = base.and_then(|dir| dir.parent().map(Path::to_path_buf));
+        }
+        base.into_iter().collect()
+    } else {
+        let src = root.map(|root| root.join("src"));
+        [file_dir, root, src.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(Path::to_path_buf)
+            .collect()
+    };
+    let rel: PathBuf = rest.split('.').filter(|part| !part.is_empty()).collect();
+    let mut files: Vec<PathBuf> = Vec::new();
+    for base in bases {
+        let dir = base.join(&rel);
+        let mut module_file = dir.clone().into_os_string();
+        module_file.push(".py");
+        let candidates = if rest.is_empty() {
+            vec![dir.join("__init__.py")]
+        } else {
+            vec![PathBuf::from(module_file), dir.join("__init__.py")]
+        };
+        for path in candidates {
+            if path.is_file() && !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn signatures(text: &str) -> Vec<Signature> {
+        scan_signatures(text, &tokenize_strings(text))
+    }
+
+    #[test]
+    fn scans_path_annotated_parameters() {
+        let text = "\
+def load(src: Path | str, mode=\"r\", *, cache: os.PathLike = None):
+    pass
+
+async def fetch(url: str,  # where from
+                dest: \"Path\") -> None: ...
+
+def raw(p: Path, /, q: Path): ...
+class Loader:
+    def method(self, path: Path): ...
+def plain(x, y=\"(\"): ...
+";
+        assert_eq!(
+            signatures(text),
+            vec![
+                Signature {
+                    name: "load".into(),
+                    args: vec![
+                        ArgSlot::Position(0),
+                        ArgSlot::Keyword("src".into()),
+                        ArgSlot::Keyword("cache".into()),
+                    ],
+                },
+                Signature {
+                    name: "fetch".into(),
+                    args: vec![ArgSlot::Position(1), ArgSlot::Keyword("dest".into())],
+                },
+                Signature {
+                    name: "raw".into(),
+                    args: vec![
+                        ArgSlot::Position(0),
+                        ArgSlot::Position(1),
+                        ArgSlot::Keyword("q".into()),
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_imports() {
+        let text = "import os, pkg.io as pio\nfrom . import helpers\nfrom pkg.io import (\n    load,  # loader\n    save as store,\n)\n\"\"\"\nfrom docs import nothing\n\"\"\"\n";
+        let imports: Vec<(String, String)> = scan_imports(text, &tokenize_strings(text))
+            .into_iter()
+            .map(|import| (import.local, import.qualified))
+            .collect();
+        let expected: Vec<(String, String)> = [
+            ("os", "os"),
+            ("pio", "pkg.io"),
+            ("helpers", ".helpers"),
+            ("load", "pkg.io.load"),
+            ("store", "pkg.io.save"),
+        ]
+        .iter()
+        .map(|(local, qualified)| (local.to_string(), qualified.to_string()))
+        .collect();
+        assert_eq!(imports, expected);
+        assert_eq!(split_module(".helpers"), Some((".", "helpers")));
+        assert_eq!(split_module("pkg.io.load"), Some(("pkg.io", "load")));
+    }
+
+    #[test]
+    fn resolves_imported_signatures_and_rescans_changes() {
+        let root = TempDir::new("signatures");
+        std::fs::create_dir_all(root.join("src").join("pkg")).unwrap();
+        let module = root.join("src").join("pkg").join("io.py");
+        std::fs::write(&module, "def load(src: Path): ...\n").unwrap();
+
+        let text = "from pkg.io import load\nimport pkg.io as pio\n";
+        let tokens = tokenize_strings(text);
+        let file = root.join("main.py");
+        let mut cache = SignatureCache::default();
+        let calls = |cache: &mut SignatureCache| -> Vec<String> {
+            cache
+                .rules_for(text, &tokens, Some(&file), Some(&root))
+                .into_iter()
+                .map(|rule| rule.call)
+                .collect()
+        };
+        let before = calls(&mut cache);
+        std::fs::write(&module, "def save(obj, dest: Path): ...\n").unwrap();
+        cache.invalidate(&module);
+        let after = calls(&mut cache);
+
+        assert_eq!(before, vec!["load".to_string(), "pio.load".to_string()]);
+        assert_eq!(after, vec!["pio.save".to_string()]);
+    }
+}
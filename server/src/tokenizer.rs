@@ -0,0 +1,566 @@
+/// One Python string literal token, with byte offsets into the document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringToken {
+    /// Offset where the literal begins, including any prefix letters.
+    pub start: usize,
+    pub content_start: usize,
+    /// Offset of the closing quote, or where an unterminated string stops:
+    /// the end of its line for single-quoted strings, the end of the document
+    /// for triple-quoted ones.
+    pub content_end: usize,
+    pub quote: char,
+    pub triple: bool,
+    pub is_raw: bool,
+    pub is_bytes: bool,
+    /// Set for f-strings and PEP 750 t-strings, whose braces are
+    /// interpolations.
+    pub is_fstring: bool,
+    /// Cleared when the letters before the quote are not a prefix Python
+    /// accepts, as in `ub"..."`; such strings are never treated as paths.
+    pub valid_prefix: bool,
+    pub terminated: bool,
+    /// Whether this string is implicitly concatenated with the previous one,
+    /// e.g. the second half of `("data/" "train.csv")`.
+    pub continues_previous: bool,
+}
+
+impl StringToken {
+    /// Offset just past the closing quote.
+    pub fn end(&self) -> usize {
+        if !self.terminated {
+            return self.content_end;
+        }
+        self.content_end + if self.triple { 3 } else { 1 }
+    }
+
+    pub fn kind(&self) -> LiteralKind {
+        LiteralKind {
+            quote: self.quote,
+            triple: self.triple,
+            is_raw: self.is_raw,
+            is_bytes: self.is_bytes,
+            is_fstring: self.is_fstring,
+        }
+    }
+}
+
+/// How a literal is written, which decides how its source text maps to the
+/// string value and back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteralKind {
+    pub quote: char,
+    pub triple: bool,
+    pub is_raw: bool,
+    pub is_bytes: bool,
+    pub is_fstring: bool,
+}
+
+impl Default for LiteralKind {
+    fn default() -> Self {
+        Self {
+            quote: '"',
+            triple: false,
+            is_raw: false,
+            is_bytes: false,
+            is_fstring: false,
+        }
+    }
+}
+
+impl LiteralKind {
+    /// The value of a piece of literal source: escape sequences are decoded
+    /// and line continuations dropped. Unrecognized escapes such as `\d` keep
+    /// their backslash, as in Python. Bytes are read as UTF-8. In f-strings,
+    /// `{{` and `}}` stand for single braces.
+    pub fn unescape(&self, raw: &str) -> String {
+        if self.is_fstring && raw.contains(['{', '}']) {
+            let plain = Self {
+                is_fstring: false,
+                ..*self
+            };
+            return plain.unescape(&raw.replace("{{", "{").replace("}}", "}"));
+        }
+        if self.is_raw {
+            return raw.to_string();
+        }
+        if self.is_bytes {
+            let mut value = Vec::with_capacity(raw.len());
+            decode(raw, true, |_, ch| {
+                if let Some(ch) = ch {
+                    match u8::try_from(u32::from(ch)) {
+                        Ok(byte) => value.push(byte),
+                        Err(_) => value.extend_from_slice(ch.to_string().as_bytes()),
+                    }
+                }
+            });
+            return String::from_utf8_lossy(&value).into_owned();
+        }
+        let mut value = String::with_capacity(raw.len());
+        decode(raw, false, |_, ch| value.extend(ch));
+        value
+    }
+
+    /// Offset into `raw` just past the last source text that decodes to a
+    /// path separator, i.e. where the segment being typed begins.
+    pub fn segment_start(&self, raw: &str) -> usize {
+        if self.is_raw {
+            return raw.rfind(['/', '\\']).map_or(0, |idx| idx + 1);
+        }
+        let mut start = 0usize;
+        decode(raw, self.is_bytes, |end, ch| {
+            if matches!(ch, Some('/' | '\\')) {
+                start = end;
+            }
+        });
+        start
+    }
+
+    /// Source text that makes the literal's value contain `text`, or `None`
+    /// when a raw literal cannot express it. Raw text ending in an odd run of
+    /// backslashes is rejected since it would escape a closing quote after it.
+    pub fn escape(&self, text: &str) -> Option<String> {
+        let mut escaped = String::with_capacity(text.len());
+        if self.is_raw {
+            let delimiter: String =
+                std::iter::repeat_n(self.quote, if self.triple { 3 } else { 1 }).collect();
+            let trailing_backslashes = text.len() - text.trim_end_matches('\\').len();
+            if text.contains(&delimiter)
+                || trailing_backslashes % 2 == 1
+                || (!self.triple && text.contains(['\n', '\r']))
+                || (self.is_bytes && !text.is_ascii())
+            {
+                return None;
+            }
+        }
+        for ch in text.chars() {
+            match ch {
+                '{' | '}' if self.is_fstring => {
+                    escaped.push(ch);
+                    escaped.push(ch);
+                }
+                _ if self.is_raw => escaped.push(ch),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                _ if ch == self.quote => {
+                    escaped.push('\\');
+                    escaped.push(ch);
+                }
+                _ if ch.is_control() => escaped.push_str(&format!("\\x{:02x}", ch as u32)),
+                _ if self.is_bytes && !ch.is_ascii() => {
+                    for byte in ch.to_string().bytes() {
+                        escaped.push_str(&format!("\\x{byte:02x}"));
+                    }
+                }
+                _ => escaped.push(ch),
+            }
+        }
+        Some(escaped)
+    }
+}
+
+/// Walks the source of a non-raw literal, calling `emit` with the offset just
+/// past each character or escape sequence and the character it stands for
+/// (`None` for a line continuation). Bytes literals have no `\u` escapes.
+fn decode(raw: &str, is_bytes: bool, mut emit: impl FnMut(usize, Option<char>)) {
+    let mut i = 0usize;
+    while let Some(ch) = raw[i..].chars().next() {
+        if ch == '\\' {
+            if let Some((len, decoded)) = parse_escape(&raw[i + 1..], is_bytes) {
+                i += 1 + len;
+                emit(i, decoded);
+                continue;
+            }
+        }
+        i += ch.len_utf8();
+        emit(i, Some(ch));
+    }
+}
+
+/// Parses the escape sequence following a backslash, returning its length and
+/// value, or `None` when the backslash stands for itself.
+fn parse_escape(rest: &str, is_bytes: bool) -> Option<(usize, Option<char>)> {
+    let first = rest.chars().next()?;
+    let simple = match first {
+        '\n' => return Some((1, None)),
+        '\r' if rest[1..].starts_with('\n') => return Some((2, None)),
+        '\\' | '\'' | '"' => first,
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0c',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\x0b',
+        '0'..='7' => {
+            let len = rest
+                .bytes()
+                .take(3)
+                .take_while(|b| (b'0'..=b'7').contains(b))
+                .count();
+            let value = u32::from_str_radix(&rest[..len], 8).ok()?;
+            return Some((len, Some(char::from_u32(value)?)));
+        }
+        'u' | 'U' if is_bytes => return None,
+        'x' | 'u' | 'U' => {
+            let digits = match first {
+                'x' => 2,
+                'u' => 4,
+                _ => 8,
+            };
+            let hex = rest.get(1..1 + digits)?;
+            if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            let value = u32::from_str_radix(hex, 16).ok()?;
+            return Some((1 + digits, Some(char::from_u32(value)?)));
+        }
+        _ => return None,
+    };
+    Some((1, Some(simple)))
+}
+
+/// Scans a whole Python document for string literals. Comments are skipped,
+/// triple-quoted strings and backslash continuations may span lines, and
+/// strings separated only by whitespace, comments, continuations or newlines
+/// inside brackets are marked as implicitly concatenated.
+pub fn tokenize_strings(text: &str) -> Vec<StringToken> {
+    let bytes = text.as_bytes();
+    let mut tokens: Vec<StringToken> = Vec::new();
+    let mut depth = 0usize;
+    // Set after a string closes; cleared by anything that breaks concatenation.
+    let mut joinable = false;
+    let mut i = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'\\' if matches!(bytes.get(i + 1), Some(b'\n')) => i += 2,
+            b'\\' if bytes.get(i + 1) == Some(&b'\r') && bytes.get(i + 2) == Some(&b'\n') => i += 3,
+            b'\n' => {
+                if depth == 0 {
+                    joinable = false;
+                }
+                i += 1;
+            }
+            b' ' | b'\t' | b'\r' | b'\x0c' => i += 1,
+            b'(' | b'[' | b'{' => {
+                depth += 1;
+                joinable = false;
+                i += 1;
+            }
+            b')' | b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                joinable = false;
+                i += 1;
+            }
+            quote @ (b'"' | b'\'') => {
+                let token = scan_string(text, i, i, quote as char, joinable);
+                i = token.end();
+                joinable = token.terminated;
+                tokens.push(token);
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let word_end = i + text[i..]
+                    .bytes()
+                    .take_while(|b| b.is_ascii_alphanumeric() || *b == b'_')
+                    .count();
+                // A word directly before a quote is the string's prefix, and a
+                // valid one does not break concatenation, as in `"a" f"b"`.
+                let Some(quote @ (b'"' | b'\'')) = bytes.get(word_end).copied() else {
+                    joinable = false;
+                    i = word_end;
+                    continue;
+                };
+                let token = scan_string(text, i, word_end, quote as char, joinable);
+                i = token.end();
+                joinable = token.terminated && token.valid_prefix;
+                tokens.push(token);
+            }
+            _ => {
+                joinable = false;
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Scans the string whose prefix starts at `start` and whose opening quote is
+/// at `quote_start`.
+fn scan_string(
+    text: &str,
+    start: usize,
+    quote_start: usize,
+    quote: char,
+    continues_previous: bool,
+) -> StringToken {
+    let bytes = text.as_bytes();
+    let flags = parse_prefix(&text[start..quote_start]);
+    let valid_prefix = flags.is_some();
+    let (is_raw, is_bytes, is_fstring) = flags.unwrap_or_default();
+    let triple = text[quote_start..].starts_with(if quote == '"' { "\"\"\"" } else { "'''" });
+    let content_start = quote_start + if triple { 3 } else { 1 };
+    let mut i = content_start;
+    let mut content_end = bytes.len();
+    let mut terminated = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            // Even in raw strings a backslash keeps the next quote or newline
+            // from ending the literal.
+            b'\\' => i += 2,
+            b'\n' if !triple => {
+                content_end = i;
+                break;
+            }
+            b if b == quote as u8 => {
+                if !triple || text[i..].starts_with(&text[quote_start..content_start]) {
+                    content_end = i;
+                    terminated = true;
+                    break;
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    StringToken {
+        start,
+        content_start,
+        content_end: content_end.min(bytes.len()),
+        quote,
+        triple,
+        is_raw,
+        is_bytes,
+        is_fstring,
+        valid_prefix,
+        terminated,
+        continues_previous: continues_previous && valid_prefix,
+    }
+}
+
+/// Reads a string prefix as `(raw, bytes, interpolated)`, or `None` when
+/// Python rejects it. Letters are case-insensitive and may come in any order.
+fn parse_prefix(prefix: &str) -> Option<(bool, bool, bool)> {
+    let mut letters: Vec<char> = prefix.chars().map(|c| c.to_ascii_lowercase()).collect();
+    letters.sort_unstable();
+    let letters: String = letters.into_iter().collect();
+    match letters.as_str() {
+        "" | "u" => Some((false, false, false)),
+        "r" => Some((true, false, false)),
+        "b" => Some((false, true, false)),
+        "br" => Some((true, true, false)),
+        "f" | "t" => Some((false, false, true)),
+        "fr" | "rt" => Some((true, false, true)),
+        _ => None,
+    }
+}
+
+/// Returns the index of the string token whose contents contain `offset`.
+pub fn token_at(tokens: &[StringToken], offset: usize) -> Option<usize> {
+    let idx = tokens.partition_point(|token| token.content_start <= offset);
+    let candidate = idx.checked_sub(1)?;
+    (offset <= tokens[candidate].content_end).then_some(candidate)
+}
+
+/// The first token of the concatenation group containing `tokens[idx]`.
+pub fn group_start(tokens: &[StringToken], mut idx: usize) -> usize {
+    while idx > 0 && tokens[idx].continues_previous {
+        idx -= 1;
+    }
+    idx
+}
+
+/// Returns the string token spanning `offset`, prefix and quotes included.
+pub fn string_containing(tokens: &[StringToken], offset: usize) -> Option<&StringToken> {
+    let idx = tokens.partition_point(|token| token.start <= offset);
+    let token = &tokens[idx.checked_sub(1)?];
+    (offset < token.end()).then_some(token)
+}
+
+/// Walks the code before `offset` backward, a byte at a time. Comments are
+/// skipped and each string literal is yielded once, as a `"` at its start.
+pub fn code_before<'a>(text: &'a str, tokens: &'a [StringToken], offset: usize) -> CodeBefore<'a> {
+    let mut code = CodeBefore {
+        text,
+        tokens,
+        pos: offset,
+    };
+    code.pos = code.code_end(offset);
+    code
+}
+
+pub struct CodeBefore<'a> {
+    text: &'a str,
+    tokens: &'a [StringToken],
+    /// End of the code not yet yielded.
+    pos: usize,
+}
+
+impl CodeBefore<'_> {
+    /// Where the code of the line ending at `end` stops: at its comment, if
+    /// it has one.
+    fn code_end(&self, end: usize) -> usize {
+        let line_start = self.text[..end].rfind('\n').map_or(0, |idx| idx + 1);
+        self.text[line_start..end]
+            .match_indices('#')
+            .map(|(idx, _)| line_start + idx)
+            .find(|&idx| string_containing(self.tokens, idx).is_none())
+            .unwrap_or(end)
+    }
+}
+
+impl Iterator for CodeBefore<'_> {
+    type Item = (usize, u8);
+
+    fn next(&mut self) -> Option<(usize, u8)> {
+        let idx = self.pos.checked_sub(1)?;
+        if let Some(token) = string_containing(self.tokens, idx) {
+            self.pos = token.start;
+            return Some((token.start, b'"'));
+        }
+        let byte = self.text.as_bytes()[idx];
+        self.pos = if byte == b'\n' {
+            self.code_end(idx)
+        } else {
+            idx
+        };
+        Some((idx, byte))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents<'a>(text: &'a str, tokens: &[StringToken]) -> Vec<&'a str> {
+        tokens
+            .iter()
+            .map(|t| &text[t.content_start..t.content_end])
+            .collect()
+    }
+
+    #[test]
+    fn tracks_triple_quoted_strings_across_lines() {
+        let text = "\"\"\"Doc with 'quotes'\nand \"more\" here\n\"\"\"\nopen('data.csv')  # 'not a string'\n";
+        let tokens = tokenize_strings(text);
+        assert_eq!(
+            contents(text, &tokens),
+            vec!["Doc with 'quotes'\nand \"more\" here\n", "data.csv"]
+        );
+        assert!(tokens[0].triple && tokens[0].terminated);
+    }
+
+    #[test]
+    fn follows_continuations_and_concatenation() {
+        let text =
+            "p = open(\"data/\"\n         rf\"train.csv\")\nq = \"a\\\nb\"\nr = \"x\"\n\"y\"\n";
+        let tokens = tokenize_strings(text);
+        assert_eq!(
+            contents(text, &tokens),
+            vec!["data/", "train.csv", "a\\\nb", "x", "y"]
+        );
+        let joined: Vec<bool> = tokens.iter().map(|t| t.continues_previous).collect();
+        assert_eq!(joined, vec![false, true, false, false, false]);
+        assert!(tokens[1].is_raw && tokens[1].is_fstring);
+    }
+
+    #[test]
+    fn reads_every_string_prefix() {
+        let text = "a = (rb'x' BR'x' u'x' Rf'x' t'x' tr'x' b'x' ub'x' fb'x')\n";
+        let tokens = tokenize_strings(text);
+        let flags: Vec<(bool, bool, bool, bool)> = tokens
+            .iter()
+            .map(|t| (t.is_raw, t.is_bytes, t.is_fstring, t.valid_prefix))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                (true, true, false, true),
+                (true, true, false, true),
+                (false, false, false, true),
+                (true, false, true, true),
+                (false, false, true, true),
+                (true, false, true, true),
+                (false, true, false, true),
+                (false, false, false, false),
+                (false, false, false, false),
+            ]
+        );
+        assert!(!tokens[8].continues_previous);
+    }
+
+    #[test]
+    fn unescapes_and_escapes_by_literal_kind() {
+        let plain = LiteralKind::default();
+        assert_eq!(
+            plain.unescape(r"C:\\data\\x\x41\u00e9\d\\\nnext"),
+            "C:\\data\\xA\u{e9}\\d\\\nnext"
+        );
+        assert_eq!(plain.unescape("a\\\nb"), "ab");
+        assert_eq!(plain.segment_start(r"run\\ep\nx"), r"run\\".len());
+        assert_eq!(plain.segment_start(r"C:\Users"), r"C:\".len());
+        assert_eq!(
+            plain.escape("it's \"x\"\\").as_deref(),
+            Some(r#"it's \"x\"\\"#)
+        );
+
+        let raw = LiteralKind {
+            is_raw: true,
+            ..plain
+        };
+        assert_eq!(raw.unescape(r"C:\data"), r"C:\data");
+        assert_eq!(raw.escape(r"C:\data").as_deref(), Some(r"C:\data"));
+        assert!(raw.escape("say \"hi\"").is_none());
+        assert!(raw.escape(r"C:\dir\").is_none());
+
+        let fstring = LiteralKind {
+            is_fstring: true,
+            ..plain
+        };
+        assert_eq!(fstring.unescape("data/{{x}}/"), "data/{x}/");
+        assert_eq!(
+            LiteralKind {
+                is_raw: true,
+                ..fstring
+            }
+            .unescape(r"{{x}}\"),
+            r"{x}\"
+        );
+        assert_eq!(raw.escape(r"C:\dir\\").as_deref(), Some(r"C:\dir\\"));
+
+        let bytes = LiteralKind {
+            is_bytes: true,
+            ..plain
+        };
+        assert_eq!(bytes.unescape(r"caf\xc3\xa9\u00e9"), "café\\u00e9");
+        assert_eq!(bytes.escape("é").as_deref(), Some(r"\xc3\xa9"));
+    }
+
+    #[test]
+    fn unterminated_string_stops_at_line_end() {
+        let text = "open(\"./da\nx = 'ok'\n";
+        let tokens = tokenize_strings(text);
+        assert!(!tokens[0].terminated);
+        assert_eq!(
+            &text[tokens[0].content_start..tokens[0].content_end],
+            "./da"
+        );
+        assert_eq!(token_at(&tokens, 10), Some(0));
+        assert_eq!(&text[tokens[1].content_start..tokens[1].content_end], "ok");
+    }
+
+    #[test]
+    fn code_before_skips_strings_and_comments() {
+        let text = "f(\"a)\", # x)\n  '''b\n)''', c";
+        let tokens = tokenize_strings(text);
+        let code: String = code_before(text, &tokens, text.len())
+            .map(|(_, byte)| byte as char)
+            .collect();
+        assert_eq!(code, "c ,\"  \n ,\"(f");
+    }
+}
@@ -332,8 +332,10 @@ mod tests {
 
     #[test]
     fn cache_dir_relative() {
-        let mut config = ExtensionConfig::default();
-        config.cache_dir = Some("my-cache".to_string());
+        let config = ExtensionConfig {
+            cache_dir: Some("my-cache".to_string()),
+            ..ExtensionConfig::default()
+        };
         let path = cache_root(&config).unwrap();
         assert_eq!(path, PathBuf::from("my-cache"));
     }